@@ -0,0 +1,185 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use wall_rules::{ExternalEngine, MctsConfig, Player, SearchLimit, best_action, mcts_action};
+
+/// Command-line flag that hands player B to the alpha-beta search.
+/// A number is how many actions ahead it looks, like `--ai 2`; a number of milliseconds is how long it thinks, like `--ai 500ms`.
+pub const AI_FLAG: &str = "--ai";
+/// Command-line flag that hands player B to the tree search instead, playing out this many quick games a move, like `--mcts 2000`.
+pub const MCTS_FLAG: &str = "--mcts";
+/// Command-line flag that hands player B to an engine program speaking the engine protocol, like `--engine ./my_bot`.
+pub const ENGINE_FLAG: &str = "--engine";
+/// How long an engine program may think about each move, in milliseconds, like `--movetime 2000`.
+pub const MOVETIME_FLAG: &str = "--movetime";
+pub const DEFAULT_MOVETIME: Duration = Duration::from_secs(1);
+/// Command-line flag that seats the computer somewhere other than B, like `--ai-player a`.
+pub const AI_PLAYER_FLAG: &str = "--ai-player";
+
+/// Which search picks the computer's moves, and how hard it thinks.
+#[derive(Debug, Clone)]
+pub enum Engine {
+    AlphaBeta(SearchLimit),
+    Mcts(MctsConfig),
+    /// A separate program, asked over its standard input and output. Only one question is asked of it at a time.
+    External {
+        engine: Arc<Mutex<ExternalEngine>>,
+        movetime: Duration,
+    },
+}
+impl Engine {
+    /// The action to play in `game`. This can take a while, so it is run away from the frame loop.
+    pub fn think(&self, game: &Game) -> Option<Action> {
+        match self {
+            Engine::AlphaBeta(limit) => best_action(game, *limit),
+            Engine::Mcts(config) => mcts_action(game, config),
+            Engine::External { engine, movetime } => {
+                let mut engine = engine.lock().ok()?;
+                engine
+                    .best_action(game, *movetime)
+                    .inspect_err(|e| println!("{} could not move: {e}", engine.path()))
+                    .ok()
+            }
+        }
+    }
+}
+
+/// The computer's seat at the table and the engine playing it.
+#[derive(Resource, Debug, Clone)]
+pub struct AiOpponent {
+    pub player: PlayerId,
+    pub engine: Engine,
+}
+impl AiOpponent {
+    /// The opponent asked for with `--ai`, `--mcts` or `--engine`, if there is one and it can be set up.
+    pub fn from_args() -> Option<Self> {
+        let engine = if let Some(text) = flag_value(AI_FLAG) {
+            Self::alpha_beta(&text)?
+        } else if let Some(text) = flag_value(MCTS_FLAG) {
+            Self::mcts(&text)?
+        } else if let Some(path) = flag_value(ENGINE_FLAG) {
+            Self::external(&path)?
+        } else {
+            return None;
+        };
+        Some(Self {
+            player: Self::seat_from_args().unwrap_or(PlayerId::B),
+            engine,
+        })
+    }
+    fn seat_from_args() -> Option<PlayerId> {
+        let text = flag_value(AI_PLAYER_FLAG)?;
+        let seat = Player::all()
+            .into_iter()
+            .find(|player| format!("{player:?}").eq_ignore_ascii_case(&text));
+        if seat.is_none() {
+            println!("{AI_PLAYER_FLAG} needs a player letter like `b`, not `{text}`");
+        }
+        seat.map(PlayerId::from)
+    }
+    fn alpha_beta(text: &str) -> Option<Engine> {
+        let limit = match text.strip_suffix("ms") {
+            Some(millis) => millis.parse().ok().map(|ms| SearchLimit::Time(Duration::from_millis(ms))),
+            None => text.parse().ok().filter(|depth| *depth > 0).map(SearchLimit::Depth),
+        };
+        if limit.is_none() {
+            println!("{AI_FLAG} needs a search depth like `2` or a time budget like `500ms`, not `{text}`");
+        }
+        limit.map(Engine::AlphaBeta)
+    }
+    fn mcts(text: &str) -> Option<Engine> {
+        let Some(playouts) = text.parse().ok().filter(|playouts| *playouts > 0) else {
+            println!("{MCTS_FLAG} needs a number of playouts like `2000`, not `{text}`");
+            return None;
+        };
+        // Seeded from the time of day, so games that follow the same line still play out differently.
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        Some(Engine::Mcts(MctsConfig::new(playouts).with_seed(seed)))
+    }
+    fn external(path: &str) -> Option<Engine> {
+        let movetime = match flag_value(MOVETIME_FLAG) {
+            Some(text) => match text.parse() {
+                Ok(millis) => Duration::from_millis(millis),
+                Err(_) => {
+                    println!("{MOVETIME_FLAG} needs a number of milliseconds like `1000`, not `{text}`");
+                    return None;
+                }
+            },
+            None => DEFAULT_MOVETIME,
+        };
+        match ExternalEngine::spawn(path) {
+            Ok(engine) => Some(Engine::External {
+                engine: Arc::new(Mutex::new(engine)),
+                movetime,
+            }),
+            Err(e) => {
+                println!("Could not use {path} as an engine: {e}");
+                None
+            }
+        }
+    }
+    fn is_to_move(&self, state: &GameState) -> bool {
+        state.active_player() == Some(self.player)
+    }
+}
+
+/// The search running on the background task pool, together with the position it was asked about.
+#[derive(Resource, Default)]
+pub struct AiThinking {
+    task: Option<(Game, Task<Option<Action>>)>,
+    /// The last position the computer came up with nothing legal for, so it is not asked about it over and over.
+    stuck: Option<Game>,
+}
+
+/// Starts the computer thinking when its turn comes up, and once it has made up its mind plays the action
+/// through the `Referee`, just like a dropped piece, then has the pieces moved to match.
+pub fn ai_turn(
+    ai: Res<AiOpponent>,
+    state: Res<State<GameState>>,
+    mut thinking: ResMut<AiThinking>,
+    mut referee: Referee,
+    mut board_changed: EventWriter<BoardChanged>,
+) {
+    // Anything still being worked out is for a position that is gone, after an undo or a load.
+    let stale = thinking
+        .task
+        .as_ref()
+        .is_some_and(|(game, _)| game != referee.game());
+    if stale || !ai.is_to_move(&state) {
+        thinking.task = None;
+    }
+    if !ai.is_to_move(&state) || thinking.stuck.as_ref() == Some(referee.game()) {
+        return;
+    }
+    let Some((_, task)) = &mut thinking.task else {
+        let game = referee.game().clone();
+        let engine = ai.engine.clone();
+        let position = game.clone();
+        let task = AsyncComputeTaskPool::get().spawn(async move { engine.think(&position) });
+        thinking.task = Some((game, task));
+        return;
+    };
+    let Some(action) = block_on(poll_once(task)) else {
+        return;
+    };
+    thinking.task = None;
+    let Some(action) = action else {
+        thinking.stuck = Some(referee.game().clone());
+        return;
+    };
+    match referee.play(action) {
+        Ok(()) => {
+            board_changed.send(BoardChanged);
+        }
+        Err(e) => {
+            println!("The computer tried {action}, which is not allowed: {e}");
+            thinking.stuck = Some(referee.game().clone());
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Plays two engines against each other without opening a window, and reports how they did.
+//!
+//! ```text
+//! cargo run --bin tournament -- <engine> <engine> [--games 10] [--movetime 1000] [--size 9] [--out results.txt]
+//! ```
+//! An engine is `ab` to let the alpha-beta search think for the move time, `ab:<depth>` to have it
+//! look a fixed number of actions ahead, `mcts:<playouts>` for the tree search, or the path to an
+//! engine program speaking the engine protocol.
+use std::{
+    fmt::Write as _,
+    fs,
+    time::{Duration, Instant},
+};
+
+use wall_rules::{
+    Action, DEFAULT_BOARD_SIZE, ExternalEngine, Game, MAX_BOARD_SIZE, MOVE_GRACE, MctsConfig, Player, SearchLimit,
+    best_action, mcts_action, walls_for_board_size,
+};
+
+const GAMES_FLAG: &str = "--games";
+const MOVETIME_FLAG: &str = "--movetime";
+const SIZE_FLAG: &str = "--size";
+const OUT_FLAG: &str = "--out";
+const DEFAULT_GAMES: usize = 10;
+const DEFAULT_MOVETIME: Duration = Duration::from_secs(1);
+/// Games still going after this many actions per square of the board are called a draw.
+const MAX_PLIES_PER_SQUARE: usize = 4;
+
+enum Bot {
+    AlphaBeta(SearchLimit),
+    Mcts(MctsConfig),
+    External(ExternalEngine),
+}
+impl Bot {
+    fn from_spec(spec: &str, movetime: Duration) -> Result<Self, String> {
+        let (kind, setting) = spec.split_once(':').unwrap_or((spec, ""));
+        let number = || {
+            setting
+                .parse::<usize>()
+                .map_err(|_| format!("`{setting}` in `{spec}` is not a number"))
+        };
+        match kind {
+            "ab" if setting.is_empty() => Ok(Bot::AlphaBeta(SearchLimit::Time(movetime))),
+            "ab" => Ok(Bot::AlphaBeta(SearchLimit::Depth(number()?))),
+            "mcts" => Ok(Bot::Mcts(MctsConfig::new(number()?))),
+            path => ExternalEngine::spawn(path)
+                .map(Bot::External)
+                .map_err(|e| format!("could not use {path} as an engine: {e}")),
+        }
+    }
+    fn think(&mut self, game: &Game, movetime: Duration) -> Result<Action, String> {
+        match self {
+            Bot::AlphaBeta(limit) => best_action(game, *limit).ok_or("no action found".to_string()),
+            Bot::Mcts(config) => mcts_action(game, config).ok_or("no action found".to_string()),
+            Bot::External(engine) => engine.best_action(game, movetime).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// One side of the match: what it is called on the command line and the bot playing it.
+struct Contestant {
+    name: String,
+    bot: Bot,
+    wins: usize,
+}
+
+/// How a single game ended.
+struct GameOutcome {
+    /// Index of the contestant who won, or `None` for a draw.
+    winner: Option<usize>,
+    plies: usize,
+    /// Why it ended, if it was not by a pawn reaching its goal.
+    note: Option<String>,
+}
+
+struct Settings {
+    games: usize,
+    movetime: Duration,
+    size: usize,
+}
+
+/// Plays one game with `first` as player A, who moves first.
+fn play_game(contestants: &mut [Contestant; 2], first: usize, settings: &Settings, seed: u64) -> GameOutcome {
+    let mut game = Game::new(settings.size, walls_for_board_size(settings.size));
+    let max_plies = MAX_PLIES_PER_SQUARE * settings.size * settings.size;
+    for contestant in contestants.iter_mut() {
+        if let Bot::Mcts(config) = &mut contestant.bot {
+            *config = config.with_seed(seed);
+        }
+    }
+    while !game.is_over() {
+        if game.history().len() >= max_plies {
+            return GameOutcome {
+                winner: None,
+                plies: game.history().len(),
+                note: Some(format!("no winner after {max_plies} actions")),
+            };
+        }
+        // Player A is `first`, player B the other one.
+        let side = if game.current_player() == Player::A { first } else { 1 - first };
+        let contestant = &mut contestants[side];
+        let started = Instant::now();
+        let plies = game.history().len();
+        let forfeit = |reason: String| GameOutcome {
+            winner: Some(1 - side),
+            plies,
+            note: Some(reason),
+        };
+        let action = match contestant.bot.think(&game, settings.movetime) {
+            Ok(action) => action,
+            Err(e) => return forfeit(format!("{} forfeits: {e}", contestant.name)),
+        };
+        if started.elapsed() > settings.movetime + MOVE_GRACE {
+            return forfeit(format!(
+                "{} lost on time, taking {} ms",
+                contestant.name,
+                started.elapsed().as_millis()
+            ));
+        }
+        if let Err(e) = game.apply(action) {
+            return forfeit(format!("{} played {action}, which is not allowed: {e}", contestant.name));
+        }
+    }
+    let winner = if game.winner() == Some(Player::A) { first } else { 1 - first };
+    GameOutcome {
+        winner: Some(winner),
+        plies: game.history().len(),
+        note: None,
+    }
+}
+
+/// Elo difference implied by scoring `score` out of one per game, if it is not all wins or all losses.
+fn elo_difference(score: f64) -> Option<f64> {
+    (score > 0.0 && score < 1.0).then(|| -400.0 * (1.0 / score - 1.0).log10())
+}
+
+/// The Elo estimate for scoring `points` in `games`, with the margin of a 95% confidence interval.
+fn elo_estimate(points: f64, games: usize) -> String {
+    let score = points / games as f64;
+    let Some(elo) = elo_difference(score) else {
+        return if score >= 1.0 { "+inf".to_string() } else { "-inf".to_string() };
+    };
+    let margin = 1.96 * (score * (1.0 - score) / games as f64).sqrt();
+    match (elo_difference(score - margin), elo_difference(score + margin)) {
+        (Some(low), Some(high)) => format!("{elo:+.0} ± {:.0}", (high - low) / 2.0),
+        _ => format!("{elo:+.0}"),
+    }
+}
+
+fn results_table(contestants: &[Contestant; 2], draws: usize, plies: usize, settings: &Settings) -> String {
+    let games = settings.games;
+    let mut table = String::new();
+    let _ = writeln!(
+        table,
+        "{} vs {}: {games} games on {size}x{size}, {} ms a move\n",
+        contestants[0].name,
+        contestants[1].name,
+        settings.movetime.as_millis(),
+        size = settings.size,
+    );
+    let width = contestants.iter().map(|c| c.name.len()).max().unwrap_or(0).max("Engine".len());
+    let _ = writeln!(table, "{:width$}  Wins  Losses  Draws  Win rate  Elo", "Engine");
+    for (index, contestant) in contestants.iter().enumerate() {
+        let losses = contestants[1 - index].wins;
+        let points = contestant.wins as f64 + draws as f64 / 2.0;
+        let _ = writeln!(
+            table,
+            "{:width$}  {:>4}  {:>6}  {:>5}  {:>7.1}%  {}",
+            contestant.name,
+            contestant.wins,
+            losses,
+            draws,
+            100.0 * contestant.wins as f64 / games as f64,
+            elo_estimate(points, games),
+        );
+    }
+    let _ = writeln!(table, "\nAverage game length: {:.1} actions", plies as f64 / games as f64);
+    table
+}
+
+/// The argument following `flag`, parsed as a number.
+fn number_flag(args: &[String], flag: &str) -> Result<Option<u64>, String> {
+    let Some(index) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    let value = args.get(index + 1).ok_or(format!("{flag} needs a value after it"))?;
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("{flag} needs a number, not `{value}`"))
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let usage = "usage: tournament <engine> <engine> [--games N] [--movetime MS] [--size N] [--out FILE]";
+    let movetime = number_flag(args, MOVETIME_FLAG)?.map_or(DEFAULT_MOVETIME, Duration::from_millis);
+    let settings = Settings {
+        games: number_flag(args, GAMES_FLAG)?.map_or(DEFAULT_GAMES, |n| n as usize).max(1),
+        movetime,
+        size: number_flag(args, SIZE_FLAG)?.map_or(DEFAULT_BOARD_SIZE, |n| n as usize),
+    };
+    if !(3..=MAX_BOARD_SIZE).contains(&settings.size) {
+        return Err(format!("{SIZE_FLAG} has to be between 3 and {MAX_BOARD_SIZE}"));
+    }
+    // The two engines are the arguments that are neither flags nor a flag's value.
+    let specs: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, arg)| !arg.starts_with("--") && (*i == 0 || !args[i - 1].starts_with("--")))
+        .map(|(_, arg)| arg)
+        .collect();
+    let [first, second] = specs[..] else {
+        return Err(usage.to_string());
+    };
+    let contestant = |spec: &String| -> Result<Contestant, String> {
+        Ok(Contestant {
+            name: spec.clone(),
+            bot: Bot::from_spec(spec, movetime)?,
+            wins: 0,
+        })
+    };
+    let mut contestants = [contestant(first)?, contestant(second)?];
+
+    let (mut draws, mut plies) = (0, 0);
+    for number in 0..settings.games {
+        // Take turns at moving first.
+        let first = number % 2;
+        let outcome = play_game(&mut contestants, first, &settings, number as u64);
+        plies += outcome.plies;
+        let result = match outcome.winner {
+            Some(winner) => {
+                contestants[winner].wins += 1;
+                format!("{} wins", contestants[winner].name)
+            }
+            None => {
+                draws += 1;
+                "draw".to_string()
+            }
+        };
+        let note = outcome.note.map(|note| format!(" ({note})")).unwrap_or_default();
+        println!(
+            "Game {}: {} moves first, {result} after {} actions{note}",
+            number + 1,
+            contestants[first].name,
+            outcome.plies,
+        );
+    }
+
+    let table = results_table(&contestants, draws, plies, &settings);
+    println!("\n{table}");
+    if let Some(index) = args.iter().position(|arg| arg == OUT_FLAG) {
+        let path = args.get(index + 1).ok_or(format!("{OUT_FLAG} needs a value after it"))?;
+        fs::write(path, &table).map_err(|e| format!("could not write {path}: {e}"))?;
+        println!("Wrote the results to {path}");
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
@@ -1,99 +1,147 @@
-use bevy::{input::mouse::MouseWheel, pbr::VolumetricFog};
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
 
 use super::*;
 
-#[derive(Component,Default,Clone,Copy)]
-pub struct ZoomCameraIdentifier;
+/// Puts the camera back where it started.
+pub const CAMERA_RESET_KEY: KeyCode = KeyCode::KeyC;
+/// Held down and dragged to circle around the board.
+pub const ORBIT_BUTTON: MouseButton = MouseButton::Right;
+/// Held down and dragged to slide the camera across the board.
+pub const PAN_BUTTON: MouseButton = MouseButton::Middle;
+/// Radians turned or tilted per pixel dragged.
+pub const ORBIT_SPEED: f32 = 0.005;
+/// How far the camera may tilt away from looking straight down at the board, in radians.
+pub const MAX_TILT: f32 = 1.3;
+/// Share of the camera's distance from the board slid per pixel dragged.
+pub const PAN_SPEED: f32 = 0.0015;
+/// Share of the camera's distance from the board each notch of the wheel moves it.
+pub const ZOOM_STEP: f32 = 0.1;
+/// Closest and furthest the camera may get, as shares of how far away it started.
+pub const MIN_ZOOM: f32 = 0.3;
+pub const MAX_ZOOM: f32 = 2.5;
+/// Touchpads scroll in pixels rather than notches; this many make up a notch.
+const PIXELS_PER_NOTCH: f32 = 100.0;
+/// How quickly the camera catches up with where it was sent. Higher is snappier.
+pub const CAMERA_SMOOTHING: f32 = 12.0;
 
-#[derive(Bundle)]
-pub struct ZoomCamera {
-    identifier: ZoomCameraIdentifier,
-    camera: Camera,
-    render_graph: Camera3d,
-    transform: Transform,
+/// Where the camera is, described from the point on the board it looks at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Orbit {
+    focus: Vec3,
+    /// Turn around the board's normal, in radians.
+    yaw: f32,
+    /// Tilt away from looking straight down at the board, in radians.
+    tilt: f32,
+    distance: f32,
 }
-impl ZoomCamera {
-    pub fn new(position: Vec3)  -> Self {
-        #[cfg(debug_assertions)]
-        println!("Zoom Camera!");
-        let identifier = ZoomCameraIdentifier;
-        let camera = Camera::default();
-        let render_graph = Camera3d::default();
-        let mut transform = Transform {
-            translation: position,
-            ..default()
-        };
-        transform.look_at(Vec3::ZERO, Vec3::Y);
+impl Orbit {
+    fn rotation(&self) -> Quat {
+        Quat::from_rotation_z(self.yaw) * Quat::from_rotation_x(self.tilt)
+    }
+    pub fn transform(&self) -> Transform {
+        let rotation = self.rotation();
+        Transform::from_translation(self.focus + rotation * Vec3::Z * self.distance).with_rotation(rotation)
+    }
+    /// The share `t` of the way from here to `to`.
+    fn lerp(&self, to: &Orbit, t: f32) -> Self {
         Self {
-            identifier,
-            camera,
-            render_graph,
-            transform,
+            focus: self.focus.lerp(to.focus, t),
+            yaw: self.yaw.lerp(to.yaw, t),
+            tilt: self.tilt.lerp(to.tilt, t),
+            distance: self.distance.lerp(to.distance, t),
         }
     }
 }
 
-#[derive(Component)]
-pub struct ControlledCameraIndentifier;
-#[derive(Bundle)]
-pub struct ControlledCamera {
-    identifier: ControlledCameraIndentifier,
-    camera: Camera,
-    render_graph: Camera3d,
-    transform: Transform,
+/// The camera looking at the board. The mouse sends it somewhere new, and it glides there.
+#[derive(Component, Debug)]
+#[require(Camera3d)]
+pub struct BoardCamera {
+    /// The view set up at the start, which the reset key goes back to.
+    home: Orbit,
+    /// Where the camera is headed.
+    target: Orbit,
+    /// Where the camera is now.
+    current: Orbit,
 }
-
-impl ControlledCamera {
-    pub fn new(position: Vec3) -> Self {
-        #[cfg(debug_assertions)]
-        println!("Controlled camera!");
-        let identifier = ControlledCameraIndentifier;
-        let camera = Camera::default();
-        let render_graph = Camera3d::default();
-        let mut transform = Transform {
-            translation: position,
-            ..default()
+impl BoardCamera {
+    /// Looking straight down at the middle of the board from `distance` away.
+    pub fn new(distance: f32) -> Self {
+        let home = Orbit {
+            focus: Vec3::ZERO,
+            yaw: 0.0,
+            tilt: 0.0,
+            distance,
         };
-        transform.look_at(Vec3::ZERO, Vec3::Y);
         Self {
-            identifier,
-            camera,
-            render_graph,
-            transform,
+            home,
+            target: home,
+            current: home,
         }
     }
+    pub fn transform(&self) -> Transform {
+        self.current.transform()
+    }
 }
 
-pub fn move_camera(
-    mut events: EventReader<KeyboardInput>,
-    time: Res<Time>,
-    mut controlled_camera_query: Query<&mut Transform, With<ControlledCameraIndentifier>>,
+/// Right-drag orbits around the board, middle-drag pans across it, the wheel zooms and the reset key goes back to the start.
+pub fn control_camera(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keypress: Res<ButtonInput<KeyCode>>,
+    motion: Res<AccumulatedMouseMotion>,
+    scroll: Res<AccumulatedMouseScroll>,
+    mut camera_query: Query<&mut BoardCamera>,
 ) {
-    for event in events.read() {
-        // Only check for characters when the key is pressed.
-        if !event.state.is_pressed() {
+    let Ok(mut camera) = camera_query.get_single_mut() else {
+        return;
+    };
+    if keypress.just_pressed(CAMERA_RESET_KEY) {
+        camera.target = camera.home;
+        return;
+    }
+    let home = camera.home;
+    let target = &mut camera.target;
+    if buttons.pressed(ORBIT_BUTTON) {
+        target.yaw -= motion.delta.x * ORBIT_SPEED;
+        target.tilt = (target.tilt - motion.delta.y * ORBIT_SPEED).clamp(0.0, MAX_TILT);
+    }
+    if buttons.pressed(PAN_BUTTON) {
+        // Across the board as seen on screen, so the board follows the mouse.
+        let rotation = Quat::from_rotation_z(target.yaw);
+        let slide = rotation * Vec3::new(-motion.delta.x, motion.delta.y, 0.0);
+        target.focus += slide * PAN_SPEED * target.distance;
+        target.focus = target.focus.clamp_length_max(home.distance);
+    }
+    let notches = match scroll.unit {
+        MouseScrollUnit::Line => scroll.delta.y,
+        MouseScrollUnit::Pixel => scroll.delta.y / PIXELS_PER_NOTCH,
+    };
+    if notches != 0.0 {
+        let distance = target.distance * (1.0 - ZOOM_STEP).powf(notches);
+        target.distance = distance.clamp(home.distance * MIN_ZOOM, home.distance * MAX_ZOOM);
+    }
+}
+
+/// Moves the camera part of the way to where it was sent, a little more each frame.
+pub fn ease_camera(time: Res<Time>, mut camera_query: Query<(&mut BoardCamera, &mut Transform)>) {
+    let share = 1.0 - (-CAMERA_SMOOTHING * time.delta_secs()).exp();
+    for (mut camera, mut transform) in camera_query.iter_mut() {
+        if camera.current == camera.target {
             continue;
         }
-        let move_dir = MoveDirections::new_event(event).to_vec3();
-        let mut cam = controlled_camera_query.single_mut();
-        cam.translation += move_dir * 25.0;
-        *cam = cam.looking_at(Vec3::splat(0.0), Vec3::Y);
+        camera.current = camera.current.lerp(&camera.target, share);
+        *transform = camera.transform();
     }
 }
 
-pub fn spawn_camera(commands: &mut Commands, pos: GridPosition){
-    let position = pos.into();
-    commands.spawn(ZoomCamera::new(position));
-        // .insert(VolumetricFog::default());
+/// How far a drag of `delta` pixels, ending at `pointer`, moves something lying on the board at `height`.
+/// Works out where both ends of the drag land on the board, so pieces stay under the mouse at any camera angle.
+pub fn drag_on_board(camera: (&Camera, &GlobalTransform), pointer: Vec2, delta: Vec2, height: f32) -> Option<Vec3> {
+    let (camera, camera_transform) = camera;
+    let on_board = |at: Vec2| {
+        let ray = camera.viewport_to_world(camera_transform, at).ok()?;
+        let distance = ray.intersect_plane(Vec3::Z * height, InfinitePlane3d::new(Vec3::Z))?;
+        Some(ray.get_point(distance))
+    };
+    Some(on_board(pointer)? - on_board(pointer - delta)?)
 }
-
-pub const ZOOM_WHEEL_SPEED_MULTIPLIER: i32 = 1;
-pub fn zoom_camera(mut mouse_wheel_event: EventReader<MouseWheel>, mut query: Query<(Entity, &mut Transform),With<ZoomCameraIdentifier>>){
-    for mouse_wheel in mouse_wheel_event.read(){
-        let (_camera_entity, mut zoom) = query.single_mut();
-        let z_mod = mouse_wheel.y * (ZOOM_WHEEL_SPEED_MULTIPLIER as f32);
-        zoom.translation += Vec3::ZERO.with_z(z_mod);
-        println!("{:?}",zoom.translation)
-
-    }
-}
\ No newline at end of file
@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use super::*;
+use wall_rules::{ChessClock, Mode, NetMessage, Player, TimeControl};
+
+/// Command-line flag that plays with chess clocks: minutes each plus seconds added per action, like `--clock 5+3`.
+pub const CLOCK_FLAG: &str = "--clock";
+
+/// The players' chess clocks. Only the clock of whoever is to move runs.
+///
+/// In a networked game the host's clocks are the real ones: it ends the game on a flag fall and sends
+/// everyone the times after every action, so the clocks at the other computers only fill in between.
+#[derive(Resource, Debug)]
+pub struct GameClock {
+    clock: ChessClock,
+    /// How many actions of the game have been paid their increment. Undo does not lower it,
+    /// so a turn that is undone and played again is only paid once; only a new game starts it over.
+    seen: usize,
+}
+impl GameClock {
+    pub fn new(clock: ChessClock, seen: usize) -> Self {
+        Self { clock, seen }
+    }
+    /// The clocks asked for with `--clock`, if any, for everyone playing `mode`.
+    pub fn from_args(mode: Mode) -> Option<Self> {
+        let text = flag_value(CLOCK_FLAG)?;
+        match text.parse::<TimeControl>() {
+            Ok(control) => Some(Self::new(ChessClock::new(control, mode.players()), 0)),
+            Err(e) => {
+                println!("{CLOCK_FLAG}: {e}");
+                None
+            }
+        }
+    }
+    pub fn clock(&self) -> &ChessClock {
+        &self.clock
+    }
+    /// Takes over the host's times.
+    pub fn set_clock(&mut self, clock: ChessClock) {
+        self.clock = clock;
+    }
+    pub fn flagged(&self, player: Player) -> bool {
+        self.clock.flagged(player)
+    }
+}
+
+/// Runs the clock of the player to move, adds the increment for every action played, and ends the game when a clock runs out.
+pub fn run_clock(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    mut clock: ResMut<GameClock>,
+    mut rules: ResMut<GameRules>,
+    mut next_state: ResMut<NextState<GameState>>,
+    link: Option<Res<NetLink>>,
+    mut outbox: ResMut<NetOutbox>,
+) {
+    let in_charge = link.as_ref().is_none_or(|link| link.is_host());
+    let played = rules.game().history().len();
+    // After an undo, the turns up to `seen` have been paid already and are not paid again when replayed.
+    if clock.seen < played {
+        for turn in clock.seen..played {
+            if let Some(player) = rules.game().player_for_turn(turn) {
+                clock.clock.add_increment(player);
+            }
+        }
+        clock.seen = played;
+        if link.is_some() && in_charge {
+            outbox.push(NetMessage::Clock(clock.clock.clone()));
+        }
+    }
+    let Some(active) = state.active_player() else {
+        return;
+    };
+    let player = Player::from(active);
+    if clock.clock.tick(player, time.delta()) && in_charge && !rules.is_over() {
+        println!("Player {active:?} ran out of time");
+        rules.forfeit(player, &mut next_state);
+        if link.is_some() {
+            outbox.push(NetMessage::Clock(clock.clock.clone()));
+            outbox.push(NetMessage::Forfeit(player));
+        }
+    }
+}
+
+/// Everyone back to full time for a rematch. The host tells the others.
+pub fn reset_clock(mut clock: ResMut<GameClock>, link: Option<Res<NetLink>>, mut outbox: ResMut<NetOutbox>) {
+    clock.clock.restart();
+    clock.seen = 0;
+    if link.is_some_and(|link| link.is_host()) {
+        outbox.push(NetMessage::Clock(clock.clock.clone()));
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct ClockHud;
+
+/// `m:ss`, with tenths of a second once under ten seconds.
+fn clock_text(left: Duration) -> String {
+    let seconds = left.as_secs();
+    if seconds < 10 {
+        format!("0:0{}.{}", seconds, left.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Shows everyone's time in the top right corner, marking the clock that is running.
+pub fn update_clock_hud(
+    mut commands: Commands,
+    clock: Res<GameClock>,
+    state: Res<State<GameState>>,
+    rules: Res<GameRules>,
+    mut hud_query: Query<&mut Text, With<ClockHud>>,
+) {
+    let active = state.active_player().map(Player::from);
+    let text = rules
+        .game()
+        .players()
+        .iter()
+        .map(|player| {
+            let marker = if active == Some(*player) { ">" } else { " " };
+            let left = clock_text(clock.clock.left(*player));
+            format!("{marker} {:?}  {left}", PlayerId::from(*player))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if let Ok(mut hud) = hud_query.get_single_mut() {
+        hud.0 = text;
+        return;
+    }
+    commands.spawn((
+        ClockHud,
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            right: Val::Px(12.0),
+            ..default()
+        },
+    ));
+}
@@ -0,0 +1,112 @@
+use super::*;
+
+/// Root of the overlay shown once someone has won.
+#[derive(Debug, Component)]
+pub struct GameOverScreen;
+
+#[derive(Debug, Component)]
+pub struct RematchButton;
+
+pub const REMATCH_KEY: KeyCode = KeyCode::Enter;
+
+pub fn spawn_game_over_screen(mut commands: Commands, rules: Res<GameRules>, clock: Option<Res<GameClock>>) {
+    let game = rules.game();
+    let Some(winner) = rules.winner() else {
+        return;
+    };
+    let message = match rules.forfeited() {
+        Some(loser) if clock.is_some_and(|clock| clock.flagged(loser)) => format!(
+            "Player {:?} wins, Player {:?} ran out of time!",
+            PlayerId::from(winner),
+            PlayerId::from(loser)
+        ),
+        Some(loser) => format!(
+            "Player {:?} wins, Player {:?} forfeited!",
+            PlayerId::from(winner),
+            PlayerId::from(loser)
+        ),
+        None => format!(
+            "Player {:?} wins in {} moves!",
+            PlayerId::from(winner),
+            game.turns_taken(winner)
+        ),
+    };
+    commands
+        .spawn((
+            GameOverScreen,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(24.0),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(message),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent
+                .spawn((
+                    RematchButton,
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(24.0), Val::Px(12.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.824, 0.412, 0.118)),
+                ))
+                .with_child((
+                    Text::new("Rematch (Enter)"),
+                    TextFont {
+                        font_size: 32.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+        });
+}
+
+pub fn despawn_game_over_screen(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<GameOverScreen>>,
+) {
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Asks for a new game, either through the button or the keyboard.
+pub fn rematch(
+    keypress: Res<ButtonInput<KeyCode>>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<RematchButton>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let clicked = button_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if clicked || keypress.just_pressed(REMATCH_KEY) {
+        next_state.set(GameState::Setup);
+    }
+}
+
+/// Starts the rules over and lets `sync_board` put the pawns back and hand out fresh piles of walls.
+pub fn reset_board(
+    config: Res<GameConfig>,
+    board: Res<BoardSize>,
+    mut rules: ResMut<GameRules>,
+    mut log: ResMut<ActionLog>,
+    mut board_changed: EventWriter<BoardChanged>,
+) {
+    *rules = GameRules::new(&config, *board);
+    log.clear();
+    board_changed.send(BoardChanged);
+}
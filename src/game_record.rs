@@ -0,0 +1,52 @@
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::*;
+use wall_rules::{DATE_TAG, GameRecord, RESULT_TAG, result_text};
+
+/// Folder finished games are written to, one record per game.
+pub const RECORD_DIR: &str = "records";
+
+fn seconds_since_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Today's date as records write it, like `2025.01.31`.
+fn today() -> String {
+    // Days since 1970 to a calendar date, following Howard Hinnant's `civil_from_days`.
+    let days = (seconds_since_epoch() / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year}.{month:02}.{day:02}")
+}
+
+fn write_record(record: &GameRecord) -> std::io::Result<String> {
+    fs::create_dir_all(RECORD_DIR)?;
+    let path = format!("{RECORD_DIR}/game-{}.txt", seconds_since_epoch());
+    fs::write(&path, record.to_string())?;
+    Ok(path)
+}
+
+/// Writes the game that just ended to `RECORD_DIR`, so it can be watched again with `--replay`.
+pub fn export_record(rules: Res<GameRules>) {
+    let mut record = GameRecord::from_game(rules.game());
+    record.set_tag(DATE_TAG, today());
+    if rules.forfeited().is_some() {
+        record.set_tag(RESULT_TAG, result_text(rules.game().mode(), rules.winner()));
+    }
+    match write_record(&record) {
+        Ok(path) => println!("Saved the game record to {path}"),
+        Err(e) => println!("Could not save the game record: {e}"),
+    }
+}
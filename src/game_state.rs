@@ -0,0 +1,289 @@
+use super::*;
+use bevy::ecs::system::SystemParam;
+use wall_rules::{Action, DEFAULT_WALLS_PER_PLAYER, Game, GameError, Mode, NetMessage, Player};
+
+/// Where we are in a game. Only the player whose turn it is may drag anything.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameState {
+    #[default]
+    Setup,
+    PlayerATurn,
+    PlayerBTurn,
+    PlayerCTurn,
+    PlayerDTurn,
+    GameOver,
+    /// Stepping through a recorded game. Nobody may drag anything.
+    Replay,
+}
+impl GameState {
+    /// The state matching whose turn it is in `game`, or `GameOver` once someone has won.
+    pub fn for_game(game: &Game) -> Self {
+        if game.is_over() {
+            return Self::GameOver;
+        }
+        match PlayerId::from(game.current_player()) {
+            PlayerId::A => Self::PlayerATurn,
+            PlayerId::B => Self::PlayerBTurn,
+            PlayerId::C => Self::PlayerCTurn,
+            PlayerId::D => Self::PlayerDTurn,
+        }
+    }
+    pub fn active_player(&self) -> Option<PlayerId> {
+        match self {
+            GameState::PlayerATurn => Some(PlayerId::A),
+            GameState::PlayerBTurn => Some(PlayerId::B),
+            GameState::PlayerCTurn => Some(PlayerId::C),
+            GameState::PlayerDTurn => Some(PlayerId::D),
+            GameState::Setup | GameState::GameOver | GameState::Replay => None,
+        }
+    }
+}
+
+/// Command-line flag that picks how many players sit around the board, `2` or `4`.
+pub const PLAYERS_FLAG: &str = "--players";
+
+/// Command-line flag that sets how many walls each player gets, instead of the usual number for the board.
+pub const WALLS_FLAG: &str = "--walls";
+
+/// Settings picked before a game starts.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct GameConfig {
+    /// Two players facing each other, or four, one on each edge.
+    pub mode: Mode,
+    /// How many walls each player gets to place over the whole game.
+    pub walls_per_player: usize,
+}
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            mode: Mode::TwoPlayer,
+            walls_per_player: DEFAULT_WALLS_PER_PLAYER,
+        }
+    }
+}
+impl GameConfig {
+    /// The usual settings for `mode` on `board`: more walls on bigger boards, split between everyone playing.
+    pub fn new(board: BoardSize, mode: Mode) -> Self {
+        Self {
+            mode,
+            walls_per_player: mode.walls_per_player(board.tiles()),
+        }
+    }
+    /// The mode picked with `--players`, if it is one there is.
+    pub fn mode_from_args() -> Option<Mode> {
+        let text = flag_value(PLAYERS_FLAG)?;
+        let mode = text.parse().ok().and_then(Mode::for_player_count);
+        if mode.is_none() {
+            println!("{PLAYERS_FLAG} has to be 2 or 4, not `{text}`");
+        }
+        mode
+    }
+    /// The walls each player gets according to `--walls`, if it is at least one and they all fit beside `board`.
+    pub fn walls_from_args(board: BoardSize, mode: Mode) -> Option<usize> {
+        let text = flag_value(WALLS_FLAG)?;
+        let room = pile_room(&board, mode);
+        match text.parse() {
+            Ok(walls) if (1..=room).contains(&walls) => Some(walls),
+            _ => {
+                println!("{WALLS_FLAG} has to be from 1 to {room} on this board, not `{text}`");
+                None
+            }
+        }
+    }
+}
+
+/// Command-line flag that starts the game from a position string instead of the usual start.
+pub const POSITION_FLAG: &str = "--position";
+
+/// The rules' view of the game. Every drop is checked against it before the board is changed.
+#[derive(Resource, Debug)]
+pub struct GameRules {
+    game: Game,
+    /// The player who lost without finishing, if the game ended that way.
+    forfeited: Option<Player>,
+}
+impl FromWorld for GameRules {
+    fn from_world(world: &mut World) -> Self {
+        let config = world.get_resource::<GameConfig>().copied().unwrap_or_default();
+        let board = world.get_resource::<BoardSize>().copied().unwrap_or_default();
+        Self::new(&config, board)
+    }
+}
+impl GameRules {
+    /// A fresh game with the pawns on their starting squares.
+    pub fn new(config: &GameConfig, board: BoardSize) -> Self {
+        Self::from_game(Game::with_mode(config.mode, board.tiles(), config.walls_per_player))
+    }
+    /// The game set up by `--position "<position string>"` on the command line, if there is one and it is legal.
+    pub fn from_args() -> Option<Self> {
+        let text = flag_value(POSITION_FLAG)?;
+        match Game::from_position_string(&text) {
+            Ok(game) => Some(Self::from_game(game)),
+            Err(e) => {
+                println!("Could not set up `{text}`: {e}");
+                None
+            }
+        }
+    }
+    pub fn from_game(game: Game) -> Self {
+        Self { game, forfeited: None }
+    }
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+    /// Jumps straight to `game`, e.g. after an undo. The board has to be told through `BoardChanged`.
+    pub fn set_game(&mut self, game: Game, next_state: &mut NextState<GameState>) {
+        self.game = game;
+        self.forfeited = None;
+        next_state.set(GameState::for_game(&self.game));
+    }
+    /// Shows `game` without handing out a turn, for watching a replay.
+    pub fn set_position(&mut self, game: Game) {
+        self.game = game;
+        self.forfeited = None;
+    }
+    /// Ends the game with `player` losing, unless it is already over.
+    pub fn forfeit(&mut self, player: Player, next_state: &mut NextState<GameState>) {
+        if self.is_over() {
+            return;
+        }
+        self.forfeited = Some(player);
+        next_state.set(GameState::GameOver);
+    }
+    pub fn forfeited(&self) -> Option<Player> {
+        self.forfeited
+    }
+    pub fn is_over(&self) -> bool {
+        self.game.is_over() || self.forfeited.is_some()
+    }
+    /// Whoever reached their goal, or after a forfeit whoever of the rest was closest to theirs.
+    pub fn winner(&self) -> Option<Player> {
+        self.game.winner().or_else(|| {
+            let loser = self.forfeited?;
+            self.game
+                .players()
+                .iter()
+                .copied()
+                .filter(|player| *player != loser)
+                .min_by_key(|player| self.game.distance_to_goal(*player))
+        })
+    }
+    /// Plays `action` for the active player and moves the state on to the next turn.
+    pub fn apply(
+        &mut self,
+        action: Action,
+        next_state: &mut NextState<GameState>,
+    ) -> Result<(), GameError> {
+        if self.forfeited.is_some() {
+            return Err(GameError::GameOver);
+        }
+        self.game.apply(action)?;
+        next_state.set(GameState::for_game(&self.game));
+        Ok(())
+    }
+}
+
+/// Hands the first turn out once `setup` has put the pieces on the board.
+pub fn start_first_turn(rules: Res<GameRules>, mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::for_game(rules.game()));
+}
+
+/// Everything needed to play an action: the rules check it, the log records it and the turn moves on.
+/// In a networked game the host also passes it on to everyone, while a joined player asks the host instead.
+#[derive(SystemParam)]
+pub struct Referee<'w> {
+    rules: ResMut<'w, GameRules>,
+    log: ResMut<'w, ActionLog>,
+    next_state: ResMut<'w, NextState<GameState>>,
+    link: Option<Res<'w, NetLink>>,
+    outbox: ResMut<'w, NetOutbox>,
+}
+impl Referee<'_> {
+    pub fn game(&self) -> &Game {
+        self.rules.game()
+    }
+    /// Plays `action` for the active player and records it in the `ActionLog`.
+    pub fn play(&mut self, action: Action) -> Result<(), GameError> {
+        if self.link.as_ref().is_some_and(|link| !link.is_host()) {
+            return self.ask_host(action);
+        }
+        self.rules.apply(action, &mut self.next_state)?;
+        self.log.record(action);
+        if self.link.is_some() {
+            self.outbox.push(NetMessage::Move(action));
+        }
+        Ok(())
+    }
+    /// Sends `action` to the host, which plays it for everyone once it has checked it.
+    /// The turn is handed on in the meantime, so nothing else can be dragged while waiting.
+    fn ask_host(&mut self, action: Action) -> Result<(), GameError> {
+        let mut after = self.rules.game().clone();
+        after.apply(action)?;
+        self.outbox.push(NetMessage::Move(action));
+        self.next_state.set(GameState::for_game(&after));
+        Ok(())
+    }
+}
+
+/// Whose pieces may be picked up at this computer: the player whose turn it is, unless they play from elsewhere on the network.
+#[derive(SystemParam)]
+pub struct LocalControl<'w> {
+    state: Res<'w, State<GameState>>,
+    link: Option<Res<'w, NetLink>>,
+}
+impl LocalControl<'_> {
+    pub fn active_player(&self) -> Option<PlayerId> {
+        let player = self.state.active_player()?;
+        self.link
+            .as_ref()
+            .is_none_or(|link| link.controls(player))
+            .then_some(player)
+    }
+}
+
+/// Sent when the rules jumped to a new position without the pieces being dragged there.
+#[derive(Debug, Event)]
+pub struct BoardChanged;
+
+/// Every pawn and wall entity on the table.
+type BoardPieces<'w, 's> = Query<'w, 's, Entity, Or<(With<MyPlayer>, With<Wall>)>>;
+
+/// Rebuilds the pawns and every wall, placed or not, to match where the rules say they are.
+pub fn sync_board(
+    mut events: EventReader<BoardChanged>,
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    piece_query: BoardPieces,
+    rules: Res<GameRules>,
+    board: Res<BoardSize>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+    let game = rules.game();
+    for entity in piece_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for player in game.players().iter().copied() {
+        spawn_player_bundle(
+            &mut commands,
+            &mut materials,
+            &mut meshes,
+            player.into(),
+            game.pawn(player).into(),
+            &board,
+        );
+        spawn_wall_pile(
+            player.into(),
+            game.walls_left(player),
+            &board,
+            &mut commands,
+            &mut materials,
+            &mut meshes,
+        );
+    }
+    for (owner, slot) in game.placed_walls() {
+        spawn_placed_wall(owner.into(), slot, &board, &mut commands, &mut materials, &mut meshes);
+    }
+}
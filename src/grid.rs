@@ -1,4 +1,5 @@
 use super::*;
+use wall_rules::Player;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
 pub enum GridType {
     Tile,
@@ -15,4 +16,26 @@ impl GridType {
 pub enum PlayerId {
     A,
     B,
+    C,
+    D,
+}
+impl From<PlayerId> for Player {
+    fn from(value: PlayerId) -> Player {
+        match value {
+            PlayerId::A => Player::A,
+            PlayerId::B => Player::B,
+            PlayerId::C => Player::C,
+            PlayerId::D => Player::D,
+        }
+    }
+}
+impl From<Player> for PlayerId {
+    fn from(value: Player) -> PlayerId {
+        match value {
+            Player::A => PlayerId::A,
+            Player::B => PlayerId::B,
+            Player::C => PlayerId::C,
+            Player::D => PlayerId::D,
+        }
+    }
 }
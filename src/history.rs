@@ -0,0 +1,85 @@
+use super::*;
+use wall_rules::{Action, Game};
+
+/// Every action played this game, including the ones taken back with undo, so they can be redone.
+#[derive(Resource, Debug, Default)]
+pub struct ActionLog {
+    actions: Vec<Action>,
+    applied: usize,
+}
+impl ActionLog {
+    /// Records a freshly played action. Anything that was undone before it can no longer be redone.
+    pub fn record(&mut self, action: Action) {
+        self.actions.truncate(self.applied);
+        self.actions.push(action);
+        self.applied = self.actions.len();
+    }
+    pub fn clear(&mut self) {
+        self.actions.clear();
+        self.applied = 0;
+    }
+    fn step_back(&mut self) -> bool {
+        if self.applied == 0 {
+            return false;
+        }
+        self.applied -= 1;
+        true
+    }
+    fn step_forward(&mut self) -> Option<Action> {
+        let action = *self.actions.get(self.applied)?;
+        self.applied += 1;
+        Some(action)
+    }
+}
+
+pub const UNDO_KEY: KeyCode = KeyCode::KeyZ;
+pub const REDO_KEY: KeyCode = KeyCode::KeyY;
+
+/// Ctrl+Z takes the last action back, Ctrl+Y plays it again.
+///
+/// Against the computer both go on until it is a person's turn again, so the computer's reply goes with the move it answered.
+pub fn undo_redo(
+    keypress: Res<ButtonInput<KeyCode>>,
+    ai: Option<Res<AiOpponent>>,
+    mut rules: ResMut<GameRules>,
+    mut log: ResMut<ActionLog>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut board_changed: EventWriter<BoardChanged>,
+) {
+    if !keypress.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let computer_to_move = |game: &Game| {
+        ai.as_ref()
+            .is_some_and(|ai| PlayerId::from(game.current_player()) == ai.player)
+    };
+    let mut game = rules.game().clone();
+    if keypress.just_pressed(UNDO_KEY) {
+        if !log.step_back() {
+            return;
+        }
+        game.undo();
+        while computer_to_move(&game) && log.step_back() {
+            game.undo();
+        }
+    } else if keypress.just_pressed(REDO_KEY) {
+        let mut redone = false;
+        while !redone || computer_to_move(&game) {
+            let Some(action) = log.step_forward() else {
+                break;
+            };
+            if let Err(e) = game.apply(action) {
+                println!("Could not redo {action:?}: {e}");
+                return;
+            }
+            redone = true;
+        }
+        if !redone {
+            return;
+        }
+    } else {
+        return;
+    }
+    rules.set_game(game, &mut next_state);
+    board_changed.send(BoardChanged);
+}
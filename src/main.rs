@@ -1,11 +1,21 @@
+mod ai;
 mod camera;
+mod clock;
+mod game_over;
+mod game_record;
+mod game_state;
 mod grid;
-mod move_directions;
+mod history;
+mod move_hints;
+mod network;
 mod player;
 mod pos;
+mod replay;
+mod save_load;
 // mod position_conversion;
 mod tiles;
 mod visibility_toggle;
+mod wall_ghost;
 mod walls;
 mod wireframe;
 
@@ -13,24 +23,40 @@ use avian3d::prelude::{PhysicsSchedulePlugin, SpatialQuery, SpatialQueryFilter,
 pub use bevy::prelude::*;
 pub use bevy::color::palettes::css::*;
 // use bevy::gizmos::grid;
-use bevy::{input::keyboard::KeyboardInput, pbr::VolumetricLight, window::PrimaryWindow};
+use bevy::pbr::VolumetricLight;
 pub use bevy::input::mouse::MouseMotion;
 use bevy::pbr::CascadeShadowConfigBuilder;
-use camera::{move_camera, spawn_camera, zoom_camera, ZoomCamera, ZoomCameraIdentifier};
+use ai::{AiOpponent, AiThinking, ai_turn};
+use clock::{GameClock, reset_clock, run_clock, update_clock_hud};
+use camera::{BoardCamera, control_camera, drag_on_board, ease_camera};
+use game_over::{despawn_game_over_screen, rematch, reset_board, spawn_game_over_screen};
+use game_state::{
+    BoardChanged, GameConfig, GameRules, GameState, LocalControl, Referee, start_first_turn,
+    sync_board,
+};
+use game_record::export_record;
+use history::{ActionLog, undo_redo};
+use replay::{Replay, glide_pieces, start_replay, step_replay, update_replay_hud};
+use save_load::save_load;
 use grid::{GridType, PlayerId};
-use move_directions::MoveDirections;
-use player::{spawn_player_bundle, MyPlayer, MyPlayerBundle};
-use pos::GridPosition;
-use tiles::TileBundle;
+use move_hints::{configure_hint_gizmos, draw_move_hints};
+use network::{NetLink, NetOutbox, may_restart, run_network, share_restart};
+use player::{spawn_player_bundle, MyPlayer};
+use pos::{BoardSize, GridPosition};
+use tiles::{TileBundle, tile_color};
 use visibility_toggle::{GizmoOutlineToggle, tag_invisible_on_hover_end, tag_visible_on_hover};
-use walls::{IsWall, spawn_wall};
+use wall_ghost::{spawn_wall_ghost, update_wall_ghost};
+use walls::{
+    DragOrigin, IsWall, RejectionFlash, Wall, fade_rejection_flash, return_to_origin, spawn_placed_wall,
+    pile_room, spawn_wall_pile, wall_shape,
+};
+use wall_rules::{Action, DEFAULT_BOARD_SIZE, Game, WallPosition};
 
 use std::{f32::consts::PI, usize};
 use wireframe::WireFrame;
 const SCALING: f32 = 50.0;
 const TILE_WIDTH: f32 = 64.0/SCALING;
 const TRENCH_WIDTH: f32 = 8.0/SCALING;
-const N_TILES: i32 = 9;
 const STEP_SIZE: f32 = TILE_WIDTH + TRENCH_WIDTH;
 pub const SKY_COLOR: Color = Color::linear_rgb(0.05, 0.05, 0.1);
 pub const WALL_HEIGHT: f32 = TILE_WIDTH;
@@ -38,20 +64,124 @@ fn main() {
     unsafe {
         std::env::set_var("WGPU_BACKEND", "vk");
     }
-    App::new()
-        .add_plugins(DefaultPlugins)
+    let replay = Replay::from_args();
+    let network = NetLink::from_args();
+    let (link, joined) = network.map_or((None, None), |(link, joined)| (Some(link), joined));
+    let position = GameRules::from_args().or(joined.map(GameRules::from_game));
+    // A replay, a set-up position or a joined game brings its own board size and number of players.
+    let starting_game = replay
+        .as_ref()
+        .map(Replay::start)
+        .or(position.as_ref().map(|rules| rules.game().clone()));
+    let board = starting_game
+        .as_ref()
+        .map(|game| BoardSize::new(game.size()))
+        .or_else(BoardSize::from_args)
+        .unwrap_or_default();
+    let mode = starting_game
+        .as_ref()
+        .map(Game::mode)
+        .or_else(GameConfig::mode_from_args)
+        .unwrap_or_default();
+    let mut config = GameConfig::new(board, mode);
+    if starting_game.is_none()
+        && let Some(walls) = GameConfig::walls_from_args(board, mode)
+    {
+        config.walls_per_player = walls;
+    }
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins)
         .add_plugins(MeshPickingPlugin)
         .add_plugins(PhysicsSchedulePlugin::default())
         .add_plugins(SpatialQueryPlugin)
         .insert_resource(ClearColor(SKY_COLOR))
         .init_gizmo_group::<MyGizmos>()
+        .init_state::<GameState>()
+        .insert_resource(board)
+        .insert_resource(config)
+        .init_resource::<GameRules>()
+        .init_resource::<ActionLog>()
+        .init_resource::<AiThinking>()
+        .init_resource::<NetOutbox>()
+        .add_event::<BoardChanged>()
         // .add_systems(Startup, simple_setup)
         .add_systems(Startup, setup)
-        .add_systems(FixedUpdate, zoom_camera)
+        .add_systems(Startup, configure_hint_gizmos)
+        .add_systems(Startup, spawn_wall_ghost)
+        .add_systems(Update, (control_camera, ease_camera).chain())
         .add_systems(Update, rotate_light)
         .add_systems(Update, draw_toggelable_visible_wireframes)
         .add_systems(Update, draw_always_visible_wireframes)
-        .run();
+        .add_systems(Update, fade_rejection_flash)
+        .add_systems(Update, draw_move_hints)
+        .add_systems(Update, update_clock_hud.run_if(resource_exists::<GameClock>))
+        .add_systems(Update, update_wall_ghost)
+        .add_systems(
+            Update,
+            (
+                (undo_redo, save_load)
+                    .run_if(not(in_state(GameState::Replay)))
+                    .run_if(not(resource_exists::<NetLink>)),
+                run_network.run_if(resource_exists::<NetLink>),
+                run_clock.run_if(resource_exists::<GameClock>),
+                sync_board,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            ai_turn
+                .run_if(resource_exists::<AiOpponent>)
+                .before(sync_board),
+        )
+        .add_systems(Update, start_first_turn.run_if(in_state(GameState::Setup)))
+        .add_systems(OnEnter(GameState::GameOver), (spawn_game_over_screen, export_record))
+        .add_systems(OnExit(GameState::GameOver), despawn_game_over_screen)
+        .add_systems(
+            Update,
+            rematch
+                .run_if(in_state(GameState::GameOver))
+                .run_if(may_restart),
+        )
+        .add_systems(
+            OnTransition {
+                exited: GameState::GameOver,
+                entered: GameState::Setup,
+            },
+            (
+                reset_board,
+                share_restart,
+                reset_clock.run_if(resource_exists::<GameClock>),
+            )
+                .chain(),
+        )
+        .add_systems(OnEnter(GameState::Replay), start_replay)
+        .add_systems(
+            Update,
+            (step_replay, update_replay_hud.run_if(resource_changed::<Replay>))
+                .chain()
+                .run_if(in_state(GameState::Replay)),
+        )
+        .add_systems(Update, glide_pieces);
+    if let Some(ai) = AiOpponent::from_args() {
+        app.insert_resource(ai);
+    }
+    // Someone who joined plays on the host's clock, if it has one.
+    if link.as_ref().is_none_or(NetLink::is_host)
+        && let Some(clock) = GameClock::from_args(mode)
+    {
+        app.insert_resource(clock);
+    }
+    if let Some(link) = link {
+        app.insert_resource(link);
+    }
+    if let Some(rules) = position {
+        app.insert_resource(rules);
+    }
+    if let Some(replay) = replay {
+        app.insert_resource(replay).insert_state(GameState::Replay);
+    }
+    app.run();
 }
 
 
@@ -60,8 +190,13 @@ pub fn setup(
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
+    mut board_changed: EventWriter<BoardChanged>,
+    board: Res<BoardSize>,
 ) {
-    commands.spawn(ZoomCamera::new(Vec3::new(0.0, 0.0, 15.5)));
+    // Far enough back to fit the whole board in view, whatever its size.
+    let camera_distance = 15.5 * board.tiles() as f32 / DEFAULT_BOARD_SIZE as f32;
+    let camera = BoardCamera::new(camera_distance);
+    commands.spawn((camera.transform(), camera));
     // Point-light
     commands.spawn((
         PointLight {
@@ -69,7 +204,7 @@ pub fn setup(
             radius: 0.1,
             shadows_enabled: true,
             intensity: 400_000f32,
-            range: STEP_SIZE*(N_TILES as f32),
+            range: board.width(),
             ..default()
         },
         VolumetricLight,
@@ -82,12 +217,13 @@ pub fn setup(
         .build(),
     ));
     
-    for (x,y) in (0..N_TILES).flat_map(|x| (0..N_TILES).map(move|y| (x,y))){
+    let n_tiles = board.tiles();
+    for (x,y) in (0..n_tiles).flat_map(|x| (0..n_tiles).map(move|y| (x,y))){
         continue; //TODO: This prevents spotlights.
         if (x+y) % 3 == 0 || (x-y)%3 == 0 || (y-x)%3 == 0 || (x+y)%5 == 0 {
             continue;
         }
-        let pos = Vec3::from(GridPosition::new(x as usize, y as usize)).with_z(TILE_WIDTH * 2.0);
+        let pos = board.translation(GridPosition::new(x, y)).with_z(TILE_WIDTH * 2.0);
         // Add VolumetricLight to spot light.
         commands.spawn((
             SpotLight {
@@ -129,7 +265,7 @@ pub fn setup(
         .build(),
     ));
 
-    let plane_dims = (N_TILES as f32) * STEP_SIZE / 2f32;
+    let plane_dims = board.width() / 2f32;
     // Ground Plane
     commands.spawn((
         Transform::from_xyz(0.0, 0.0, -1.0),
@@ -144,44 +280,47 @@ pub fn setup(
         })),
     ));
 
-    spawn_player_bundle(&mut commands, &mut materials, &mut meshes, PlayerId::A);
-    spawn_player_bundle(&mut commands, &mut materials, &mut meshes, PlayerId::B);
+    spawn_grid(&board, &mut commands, &mut materials, &mut meshes);
+    // The pawns and walls are put wherever the rules say they start.
+    board_changed.send(BoardChanged);
+}
 
-    spawn_grid(&mut commands, &mut materials, &mut meshes);
-    let start_pos = Vec3::new(STEP_SIZE * (N_TILES as f32 - 1.5), 0.0, 0.0);
-    let n_walls = 20;
-    spawn_wall(
-        start_pos,
-        n_walls,
-        &mut commands,
-        &mut materials,
-        &mut meshes,
-    );
+/// The argument following `flag` on the command line, like the path in `--replay game.txt`.
+pub fn flag_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != flag);
+    args.next()?;
+    let value = args.next();
+    if value.is_none() {
+        println!("{flag} needs a value after it");
+    }
+    value
 }
 
 fn spawn_grid<'a>(
+    board: &BoardSize,
     commands: &'a mut Commands,
     materials: &'a mut ResMut<Assets<StandardMaterial>>,
     meshes: &'a mut ResMut<Assets<Mesh>>,
 ) {
-    for (x, y) in (0..N_TILES as usize).flat_map(|x| (0..N_TILES as usize).map(move |y| (x, y))) {
+    let n_tiles = board.tiles();
+    for (x, y) in (0..n_tiles).flat_map(|x| (0..n_tiles).map(move |y| (x, y))) {
         let grid_position = GridPosition::new(x, y);
         commands
-            .spawn(TileBundle::new(grid_position, GridType::Tile, materials, meshes).unwrap())
+            .spawn(TileBundle::new(grid_position, GridType::Tile, board, materials, meshes).unwrap())
             .observe(tag_invisible_on_hover_end)
             .observe(tag_visible_on_hover);
-        if x as i32 != N_TILES - 1 {
+        if x != n_tiles - 1 {
             commands
                 .spawn(
-                    TileBundle::new(grid_position, GridType::Vertical, materials, meshes).unwrap(),
+                    TileBundle::new(grid_position, GridType::Vertical, board, materials, meshes).unwrap(),
                 )
                 .observe(tag_invisible_on_hover_end)
                 .observe(tag_visible_on_hover);
         }
-        if y as i32 != N_TILES - 1 {
+        if y != n_tiles - 1 {
             commands
                 .spawn(
-                    TileBundle::new(grid_position, GridType::Horizontal, materials, meshes)
+                    TileBundle::new(grid_position, GridType::Horizontal, board, materials, meshes)
                         .unwrap(),
                 )
                 .observe(tag_invisible_on_hover_end)
@@ -204,23 +343,53 @@ fn rotate_light(
     }
 }
 /// When an object is "Dragged" (prolonged click), the object follows the mouse.
+/// Nothing moves unless it is the turn of someone at this computer, and walls only move for their owner.
+/// Only the left button picks things up; the others move the camera.
 fn drag(
     hit: Trigger<Pointer<Drag>>,
-    mut target_query: Query<(Entity, &mut Transform), With<IsDraggable>>,
+    mut target_query: Query<(Entity, &mut Transform, Option<&Wall>), With<IsDraggable>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<BoardCamera>>,
+    control: LocalControl,
 ) {
-    for (_, mut target) in target_query.iter_mut().filter(|(id,_)| *id == hit.target) {
-        let pointer_location = hit.delta;
-        target.translation += Vec3::new(pointer_location.x, -pointer_location.y, 0.0) / SCALING;
+    let Some(active) = control.active_player() else {
+        return;
+    };
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    if hit.button != PointerButton::Primary {
+        return;
+    }
+    for (_, mut target, wall) in target_query.iter_mut().filter(|(id,_,_)| *id == hit.target) {
+        if wall.is_some_and(|wall| wall.owner() != active) {
+            return;
+        }
+        let pointer = hit.pointer_location.position;
+        if let Some(step) = drag_on_board(camera, pointer, hit.delta, target.translation.z) {
+            target.translation += step;
+        }
     } 
 }
 
 //TODO: Correct collisions.
+/// Pawns only follow the mouse for the player whose turn it is, if they play at this computer.
 fn drag_with_collision(
     hit: Trigger<Pointer<Drag>>,
-    mut target_query: Query<(Entity, &mut Transform), With<IsCollidingDraggable>>,
+    mut target_query: Query<(Entity, &mut Transform, &MyPlayer), With<IsCollidingDraggable>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<BoardCamera>>,
     spatial_query: SpatialQuery,
+    control: LocalControl,
 ) {
-    for (target_entity, mut target) in target_query.iter_mut().filter(|(id,_)| *id == hit.target) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    if hit.button != PointerButton::Primary {
+        return;
+    }
+    for (target_entity, mut target, player) in target_query.iter_mut().filter(|(id,_,_)| *id == hit.target) {
+        if control.active_player() != Some(player.player_id()) {
+            return;
+        }
         let distance = &hit.event().distance;
         let dir = match Dir3::from_xyz(target.translation.x + distance.x, target.translation.y + distance.y, 0.0){
             Ok(v) => v,
@@ -233,64 +402,120 @@ fn drag_with_collision(
             println!("Oops, hit a wall! {ray_hit:?}");
             return;
         }
-        let pointer_location = hit.delta;
-        target.translation += Vec3::new(pointer_location.x, -pointer_location.y, 0.0) / SCALING;
+        let pointer = hit.pointer_location.position;
+        if let Some(step) = drag_on_board(camera, pointer, hit.delta, target.translation.z) {
+            target.translation += step;
+        }
     }
 }
 
+type HoverableWalls<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static mut Transform, &'static mut WireFrame, &'static mut Wall),
+    (With<IsDraggable>, With<IsWall>),
+>;
+
 fn rotate_hovered_wall(
     hit: Trigger<Pointer<Over>>,
     keypress: Res<ButtonInput<KeyCode>>,
-    mut target_query: Query<
-        (Entity, &mut Transform, &mut WireFrame),
-        (With<IsDraggable>, With<IsWall>)>
+    mut target_query: HoverableWalls,
 ) {
     if keypress.just_released(KeyCode::KeyR) {
         let target_id = hit.target;
-        for (entity, mut target, mut wireframe) in target_query.iter_mut() {
+        for (entity, mut target, mut wireframe, mut wall) in target_query.iter_mut() {
             if target_id != entity {
                 continue;
             }
             target.rotate(Quat::from_axis_angle(Vec3::Z, PI / 2f32));
             wireframe.rotate();
+            wall.rotate();
         }
     }
 }
 /// Snaps to an integer position in a grid defined mathematically.
+/// A drop on a new tile is the active player's move for this turn; illegal moves go back where they came from.
 fn snap_drop_tile(
     hit: Trigger<Pointer<DragEnd>>,
-    mut player_query: Query<(Entity, &mut Transform), With<IsTileSnappable>>,
+    mut player_query: Query<(Entity, &mut Transform, &mut MyPlayer), With<IsTileSnappable>>,
+    mut referee: Referee,
+    control: LocalControl,
+    board: Res<BoardSize>,
 ) {
+    if hit.button != PointerButton::Primary {
+        return;
+    }
     let target_id = hit.target;
-    for (entity, mut target) in player_query.iter_mut() {
+    for (entity, mut target, mut player) in player_query.iter_mut() {
         if target_id != entity {
             continue;
         }
-        let pos: GridPosition = target.translation.into();
-        let next = pos.into();
-        target.translation = next;
+        let pos = board.grid_position(target.translation);
+        if control.active_player() == Some(player.player_id()) && pos != player.pos() {
+            match referee.play(Action::Move(pos.into())) {
+                Ok(()) => player.set_pos(pos),
+                Err(e) => println!("Move rejected to {pos}: {e}"),
+            }
+        }
+        target.translation = board.translation(player.pos());
     }
 }
 
+/// The trench crossing a wall centred at `translation` would snap to, named by the tile below and left of it.
+fn wall_slot(board: &BoardSize, translation: Vec3) -> GridPosition {
+    let mod1 = (TRENCH_WIDTH - TILE_WIDTH) / 2f32;
+    let modified_mouse_pos = translation + Vec3::new(-TILE_WIDTH / 2f32, mod1, 0.0);
+    let pos = board.grid_position(modified_mouse_pos);
+    let (mut x, mut y) = pos.as_tuple();
+    x = x.min(board.tiles() - 2);
+    y = y.min(board.tiles() - 2);
+    GridPosition::new(x,y)
+}
+
+/// Centre of the trench crossing to the upper right of `slot`.
+fn wall_slot_translation(board: &BoardSize, slot: GridPosition) -> Vec3 {
+    let limited_pos = board.translation(slot);
+    limited_pos + Vec3::new(STEP_SIZE / 2f32, STEP_SIZE / 2f32, 0.0)
+}
+
 /// Snaps to an integer position in a grid defined mathematically.
+/// Placing a wall is the active player's action for this turn, after which the wall stays put.
+/// Drops that would break the rules send the wall back to where it was picked up.
 fn snap_drop_wall(
     hit: Trigger<Pointer<DragEnd>>,
-    mut player_query: Query<(Entity, &mut Transform), With<IsWallSnappable>>,
+    mut commands: Commands,
+    mut wall_query: Query<
+        (&mut Transform, &mut Wall, &mut WireFrame, Option<&DragOrigin>),
+        With<IsWallSnappable>,
+    >,
+    mut referee: Referee,
+    control: LocalControl,
+    board: Res<BoardSize>,
 ) {
-    let target_id = hit.target;
-    for (entity, mut target) in player_query.iter_mut() {
-        if target_id != entity {
-            continue;
+    let Ok((mut target, mut wall, mut wireframe, origin)) = wall_query.get_mut(hit.target) else {
+        return;
+    };
+    if hit.button != PointerButton::Primary {
+        return;
+    }
+    commands.entity(hit.target).remove::<DragOrigin>();
+    if wall.slot().is_some() || control.active_player() != Some(wall.owner()) {
+        return;
+    }
+    let slot = WallPosition::new(wall_slot(&board, target.translation).into(), wall.orientation());
+    match referee.play(Action::Wall(slot)) {
+        Ok(()) => {
+            target.translation = wall_slot_translation(&board, slot.pos.into());
+            wall.set_slot(Some(slot));
+            commands.entity(hit.target).remove::<IsDraggable>();
+        }
+        Err(e) => {
+            println!("Wall rejected at {slot}: {e}");
+            if let Some(origin) = origin {
+                return_to_origin(origin, &mut target, &mut wall, &mut wireframe);
+            }
+            commands.entity(hit.target).insert(RejectionFlash::default());
         }
-        let mod1 = (TRENCH_WIDTH - TILE_WIDTH) / 2f32;
-        let modified_mouse_pos = target.translation + Vec3::new(-TILE_WIDTH / 2f32, mod1, 0.0);
-        let pos: GridPosition = modified_mouse_pos.into();
-        let (mut x, mut y) = pos.as_tuple();
-        x = x.min((N_TILES-2) as usize);
-        y = y.min((N_TILES-2) as usize);
-        let limited_pos: Vec3 = GridPosition::new(x,y).into();
-        let next = limited_pos + Vec3::new(STEP_SIZE / 2f32, STEP_SIZE / 2f32, 0.0);
-        target.translation = next;
     }
 }
 
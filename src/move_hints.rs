@@ -0,0 +1,83 @@
+use super::*;
+use wall_rules::Player;
+
+pub const LEGAL_MOVE_COLOR: Srgba = LIME;
+
+/// Marks the pawn that is currently being dragged by its owner.
+#[derive(Debug, Component)]
+pub struct IsBeingDragged;
+
+fn legal_moves(rules: &GameRules, player: &MyPlayer) -> Vec<GridPosition> {
+    rules
+        .game()
+        .legal_pawn_moves(Player::from(player.player_id()))
+        .into_iter()
+        .map(GridPosition::from)
+        .collect()
+}
+
+pub fn configure_hint_gizmos(mut config_store: ResMut<GizmoConfigStore>) {
+    let (config, _) = config_store.config_mut::<MyGizmos>();
+    config.line_width = 6.0;
+}
+
+/// Dims every tile the picked up pawn cannot reach this turn.
+pub fn start_move_hints(
+    hit: Trigger<Pointer<DragStart>>,
+    mut commands: Commands,
+    player_query: Query<&MyPlayer>,
+    tile_query: Query<(&GridPosition, &GridType, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    rules: Res<GameRules>,
+    control: LocalControl,
+) {
+    let Ok(player) = player_query.get(hit.target) else {
+        return;
+    };
+    if hit.button != PointerButton::Primary || control.active_player() != Some(player.player_id()) {
+        return;
+    }
+    commands.entity(hit.target).insert(IsBeingDragged);
+    let legal = legal_moves(&rules, player);
+    for (pos, _, material) in tile_query.iter().filter(|(_, t, _)| **t == GridType::Tile) {
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = tile_color(!legal.contains(pos));
+        }
+    }
+}
+
+pub fn end_move_hints(
+    hit: Trigger<Pointer<DragEnd>>,
+    mut commands: Commands,
+    dragged_query: Query<(), With<IsBeingDragged>>,
+    tile_query: Query<(&GridType, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if dragged_query.get(hit.target).is_err() {
+        return;
+    }
+    commands.entity(hit.target).remove::<IsBeingDragged>();
+    for (_, material) in tile_query.iter().filter(|(t, _)| **t == GridType::Tile) {
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = tile_color(false);
+        }
+    }
+}
+
+/// Outlines every tile the dragged pawn may be dropped on.
+pub fn draw_move_hints(
+    dragged_query: Query<&MyPlayer, With<IsBeingDragged>>,
+    tile_query: Query<(&GridPosition, &GridType, &Transform, &WireFrame)>,
+    rules: Res<GameRules>,
+    mut gizmos: Gizmos<MyGizmos>,
+) {
+    for player in dragged_query.iter() {
+        let legal = legal_moves(&rules, player);
+        for (_, _, transform, frame) in tile_query
+            .iter()
+            .filter(|(pos, t, _, _)| **t == GridType::Tile && legal.contains(pos))
+        {
+            frame.draw_colored(transform.translation, LEGAL_MOVE_COLOR.into(), &mut gizmos);
+        }
+    }
+}
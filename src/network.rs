@@ -0,0 +1,585 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use super::*;
+use bevy::{
+    ecs::system::SystemParam,
+    tasks::{IoTaskPool, Task, block_on, poll_once},
+};
+use wall_rules::{
+    Client, DEFAULT_PORT, GameError, Host, NetError, NetEvent, NetMessage, PeerId, Player, SessionToken,
+};
+
+/// Command-line flag that hosts a game for others to join, optionally on a port other than `DEFAULT_PORT`, like `--host 7878`.
+pub const HOST_FLAG: &str = "--host";
+/// Command-line flag that joins a hosted game at an address, like `--join 192.168.1.20:7878`.
+pub const JOIN_FLAG: &str = "--join";
+/// Command-line flag that follows a hosted game without playing in it, like `--watch 192.168.1.20:7878`.
+pub const WATCH_FLAG: &str = "--watch";
+/// Command-line flag that, together with `--join`, takes back a seat after a restart, using the token printed on joining.
+pub const TOKEN_FLAG: &str = "--token";
+/// Command-line flag for the host: how many seconds a player who lost the connection has to come back before forfeiting, like `--grace 30`.
+pub const GRACE_FLAG: &str = "--grace";
+pub const DEFAULT_GRACE: Duration = Duration::from_secs(60);
+/// How long a player who lost the connection waits between attempts to get back in.
+pub const REJOIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Getting back to the host after the connection dropped.
+#[derive(Debug)]
+pub enum Reconnect {
+    /// Waiting until this moment before the next attempt.
+    Waiting(Instant),
+    /// Asking the host, which can take a while, so it is done away from the frame loop.
+    Trying(Task<Result<(Client, Game), NetError>>),
+}
+
+/// This instance's end of a game played over the network.
+///
+/// The host plays player A and keeps the game everyone plays; each player who joins gets the next free seat.
+/// Anyone watching gets every move too, but has no seat and so never picks up a piece.
+#[derive(Resource, Debug)]
+pub enum NetLink {
+    Host {
+        host: Host,
+        /// The seat each connection was handed.
+        seats: HashMap<PeerId, Player>,
+        /// Every seat handed out, by the token that takes it back.
+        sessions: HashMap<SessionToken, Player>,
+        /// Seats whose player lost the connection, and since when.
+        away: HashMap<Player, Instant>,
+        /// How long a player who lost the connection has to come back.
+        grace: Duration,
+    },
+    Client {
+        client: Client,
+        /// `None` when only watching.
+        seat: Option<Player>,
+        /// Where the host is, to get back to it.
+        address: String,
+        /// What takes the seat back after losing the connection. Nobody watching has one.
+        token: Option<SessionToken>,
+        /// Set while the connection to the host is lost.
+        lost: Option<Reconnect>,
+    },
+}
+impl NetLink {
+    /// The link asked for with `--host`, `--join` or `--watch`. Joining and watching wait for the host to answer
+    /// and also return the game so far, which sets the board size and the number of players.
+    pub fn from_args() -> Option<(Self, Option<Game>)> {
+        if std::env::args().any(|arg| arg == HOST_FLAG) {
+            return Self::host_from_args().map(|link| (link, None));
+        }
+        if let Some(address) = flag_value(WATCH_FLAG) {
+            return match Client::watch(address.as_str()) {
+                Ok((client, game)) => {
+                    println!("Watching the game at {address}");
+                    let link = NetLink::Client {
+                        client,
+                        seat: None,
+                        address,
+                        token: None,
+                        lost: None,
+                    };
+                    Some((link, Some(game)))
+                }
+                Err(e) => {
+                    println!("Could not watch {address}: {e}");
+                    None
+                }
+            };
+        }
+        let address = flag_value(JOIN_FLAG)?;
+        let joined = match flag_value(TOKEN_FLAG) {
+            Some(text) => match text.parse() {
+                // Nothing has been seen yet, so every action comes in as a move after the start.
+                Ok(token) => Client::rejoin(address.as_str(), token, 0)
+                    .map(|(client, seat, game)| (client, seat, token, game)),
+                Err(_) => {
+                    println!("{TOKEN_FLAG} needs the session token printed on joining, not `{text}`");
+                    return None;
+                }
+            },
+            None => Client::join(address.as_str()),
+        };
+        match joined {
+            Ok((client, seat, token, game)) => {
+                println!("Joined {address} as player {:?}", PlayerId::from(seat));
+                println!("To get back in after a restart, add `{TOKEN_FLAG} {token}`");
+                let link = NetLink::Client {
+                    client,
+                    seat: Some(seat),
+                    address,
+                    token: Some(token),
+                    lost: None,
+                };
+                Some((link, Some(game)))
+            }
+            Err(e) => {
+                println!("Could not join {address}: {e}");
+                None
+            }
+        }
+    }
+    fn host_from_args() -> Option<Self> {
+        let port = match std::env::args().skip_while(|arg| arg != HOST_FLAG).nth(1) {
+            Some(text) if !text.starts_with("--") => match text.parse() {
+                Ok(port) => port,
+                Err(_) => {
+                    println!("{HOST_FLAG} needs a port number like `{DEFAULT_PORT}`, not `{text}`");
+                    return None;
+                }
+            },
+            _ => DEFAULT_PORT,
+        };
+        let grace = match flag_value(GRACE_FLAG) {
+            Some(text) => match text.parse() {
+                Ok(seconds) => Duration::from_secs(seconds),
+                Err(_) => {
+                    println!("{GRACE_FLAG} needs a number of seconds like `30`, not `{text}`");
+                    return None;
+                }
+            },
+            None => DEFAULT_GRACE,
+        };
+        match Host::bind(port) {
+            Ok(host) => {
+                println!("Hosting on port {}, waiting for players to join", host.port());
+                Some(NetLink::Host {
+                    host,
+                    seats: HashMap::new(),
+                    sessions: HashMap::new(),
+                    away: HashMap::new(),
+                    grace,
+                })
+            }
+            Err(e) => {
+                println!("Could not host on port {port}: {e}");
+                None
+            }
+        }
+    }
+    pub fn is_host(&self) -> bool {
+        matches!(self, NetLink::Host { .. })
+    }
+    /// The seat played at this computer, if it is not just watching.
+    pub fn seat(&self) -> Option<Player> {
+        match self {
+            NetLink::Host { .. } => Some(Player::A),
+            NetLink::Client { seat, .. } => *seat,
+        }
+    }
+    /// Whether `player`'s pieces are moved at this computer rather than somewhere else on the network.
+    pub fn controls(&self, player: PlayerId) -> bool {
+        self.seat() == Some(Player::from(player))
+    }
+    /// The first seat in `game` nobody has been handed yet.
+    fn free_seat(game: &Game, sessions: &HashMap<SessionToken, Player>) -> Option<Player> {
+        game.players()
+            .iter()
+            .copied()
+            .find(|player| *player != Player::A && !sessions.values().any(|seat| seat == player))
+    }
+}
+
+/// Messages waiting to go out. The host sends them to everyone, a joined player to the host.
+#[derive(Resource, Debug, Default)]
+pub struct NetOutbox {
+    messages: Vec<NetMessage>,
+}
+impl NetOutbox {
+    pub fn push(&mut self, message: NetMessage) {
+        self.messages.push(message);
+    }
+}
+
+/// Only the host may start a rematch in a networked game; the others follow its `sync`.
+pub fn may_restart(link: Option<Res<NetLink>>) -> bool {
+    link.is_none_or(|link| link.is_host())
+}
+
+/// Tells everyone who joined to start over from the host's fresh game.
+pub fn share_restart(rules: Res<GameRules>, link: Option<Res<NetLink>>, mut outbox: ResMut<NetOutbox>) {
+    if link.is_some_and(|link| link.is_host()) {
+        outbox.push(NetMessage::Sync(Box::new(rules.game().clone())));
+    }
+}
+
+/// This computer's game, which `run_network` keeps in step with the host.
+#[derive(SystemParam)]
+pub struct SharedGame<'w> {
+    rules: ResMut<'w, GameRules>,
+    log: ResMut<'w, ActionLog>,
+    next_state: ResMut<'w, NextState<GameState>>,
+    board_changed: EventWriter<'w, BoardChanged>,
+}
+impl SharedGame<'_> {
+    /// Jumps to `game` from the host, rebuilding the log to match.
+    fn follow(&mut self, game: Game) {
+        self.log.clear();
+        for action in game.history() {
+            self.log.record(*action);
+        }
+        self.rules.set_game(game, &mut self.next_state);
+        self.board_changed.send(BoardChanged);
+    }
+}
+
+/// Tells `peer` how much time everyone has left, if the game is played on the clock.
+fn send_clock(host: &mut Host, peer: PeerId, clock: Option<&GameClock>) {
+    if let Some(clock) = clock {
+        host.send(peer, &NetMessage::Clock(clock.clock().clone()));
+    }
+}
+
+/// Reads what came in over the network, acts on it, and sends whatever is waiting in the `NetOutbox`.
+///
+/// The host checks every requested move against its rules, plays it and passes it on to everyone.
+/// It keeps the seat of a player who lost the connection for the grace period, after which that player forfeits.
+/// A joined player only ever plays what the host sends, and keeps trying to get back in if the connection drops.
+pub fn run_network(
+    mut commands: Commands,
+    mut link: ResMut<NetLink>,
+    mut clock: Option<ResMut<GameClock>>,
+    mut outbox: ResMut<NetOutbox>,
+    mut shared: SharedGame,
+) {
+    let size = shared.rules.game().size();
+    match &mut *link {
+        NetLink::Host {
+            host,
+            seats,
+            sessions,
+            away,
+            grace,
+        } => {
+            for event in host.poll() {
+                let (peer, line) = match event {
+                    NetEvent::Line(peer, line) => (peer, line),
+                    NetEvent::Connected(_) => continue,
+                    NetEvent::Closed(peer) => {
+                        if let Some(seat) = seats.remove(&peer) {
+                            println!(
+                                "Player {:?} lost the connection and has {} seconds to come back",
+                                PlayerId::from(seat),
+                                grace.as_secs()
+                            );
+                            away.insert(seat, Instant::now());
+                        }
+                        continue;
+                    }
+                };
+                match NetMessage::parse(&line, size) {
+                    Ok(NetMessage::Join) => match NetLink::free_seat(shared.rules.game(), sessions) {
+                        Some(seat) => {
+                            let token = SessionToken::new();
+                            sessions.insert(token, seat);
+                            seats.insert(peer, seat);
+                            let game = Box::new(shared.rules.game().clone());
+                            host.send(peer, &NetMessage::Welcome { seat, token, game });
+                            send_clock(host, peer, clock.as_deref());
+                            println!("Player {:?} joined the game", PlayerId::from(seat));
+                        }
+                        None => {
+                            host.send(peer, &NetMessage::Full);
+                            host.disconnect(peer);
+                        }
+                    },
+                    Ok(NetMessage::Watch) => {
+                        host.send(peer, &NetMessage::Sync(Box::new(shared.rules.game().clone())));
+                        send_clock(host, peer, clock.as_deref());
+                        println!("Someone started watching the game");
+                    }
+                    Ok(NetMessage::Rejoin { token, seen }) => {
+                        let Some(&seat) = sessions.get(&token) else {
+                            let reason = "that session is not part of this game".to_string();
+                            host.send(peer, &NetMessage::Reject(reason));
+                            host.disconnect(peer);
+                            continue;
+                        };
+                        // A connection the seat had before may not have noticed it is gone yet.
+                        let stale: Vec<PeerId> = seats
+                            .iter()
+                            .filter(|(_, taken)| **taken == seat)
+                            .map(|(old, _)| *old)
+                            .collect();
+                        for old in stale {
+                            seats.remove(&old);
+                            host.disconnect(old);
+                        }
+                        seats.insert(peer, seat);
+                        away.remove(&seat);
+                        let mut snapshot = shared.rules.game().clone();
+                        while snapshot.history().len() > seen {
+                            snapshot.undo();
+                        }
+                        let missed = shared.rules.game().history()[snapshot.history().len()..].to_vec();
+                        let game = Box::new(snapshot);
+                        host.send(peer, &NetMessage::Welcome { seat, token, game });
+                        for action in missed {
+                            host.send(peer, &NetMessage::Move(action));
+                        }
+                        send_clock(host, peer, clock.as_deref());
+                        if let Some(loser) = shared.rules.forfeited() {
+                            host.send(peer, &NetMessage::Forfeit(loser));
+                        }
+                        println!("Player {:?} is back", PlayerId::from(seat));
+                    }
+                    Ok(NetMessage::Move(action)) => {
+                        let result = match seats.get(&peer) {
+                            Some(seat) if *seat == shared.rules.game().current_player() => {
+                                shared.rules.apply(action, &mut shared.next_state)
+                            }
+                            _ => Err(GameError::IllegalMove),
+                        };
+                        match result {
+                            Ok(()) => {
+                                println!("{:?}: {action}", PlayerId::from(seats[&peer]));
+                                shared.log.record(action);
+                                outbox.push(NetMessage::Move(action));
+                                shared.board_changed.send(BoardChanged);
+                            }
+                            Err(e) => {
+                                host.send(peer, &NetMessage::Reject(format!("{action}: {e}")));
+                                host.send(peer, &NetMessage::Sync(Box::new(shared.rules.game().clone())));
+                            }
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => println!("Ignored a message from a player: {e}"),
+                }
+            }
+            let gone: Vec<Player> = away
+                .iter()
+                .filter(|(_, since)| since.elapsed() >= *grace)
+                .map(|(seat, _)| *seat)
+                .collect();
+            for seat in gone {
+                away.remove(&seat);
+                if !shared.rules.is_over() {
+                    println!("Player {:?} did not come back in time and forfeits", PlayerId::from(seat));
+                    shared.rules.forfeit(seat, &mut shared.next_state);
+                    outbox.push(NetMessage::Forfeit(seat));
+                }
+            }
+            for message in outbox.messages.drain(..) {
+                host.broadcast(&message);
+            }
+        }
+        NetLink::Client {
+            client,
+            address,
+            token,
+            lost,
+            ..
+        } => {
+            match lost {
+                Some(Reconnect::Waiting(at)) if Instant::now() >= *at => {
+                    let seen = shared.rules.game().history().len();
+                    let (address, token) = (address.clone(), *token);
+                    let task = IoTaskPool::get().spawn(async move {
+                        match token {
+                            Some(token) => Client::rejoin(address.as_str(), token, seen)
+                                .map(|(client, _, game)| (client, game)),
+                            None => Client::watch(address.as_str()),
+                        }
+                    });
+                    *lost = Some(Reconnect::Trying(task));
+                }
+                Some(Reconnect::Trying(task)) => match block_on(poll_once(task)) {
+                    Some(Ok((back, game))) => {
+                        println!("Back in touch with the host");
+                        *client = back;
+                        *lost = None;
+                        shared.follow(game);
+                    }
+                    Some(Err(e)) => {
+                        println!("Could not get back to the host yet: {e}");
+                        *lost = Some(Reconnect::Waiting(Instant::now() + REJOIN_INTERVAL));
+                    }
+                    None => {}
+                },
+                _ => {}
+            }
+            for event in client.poll() {
+                let line = match event {
+                    NetEvent::Line(_, line) => line,
+                    NetEvent::Connected(_) => continue,
+                    NetEvent::Closed(_) => {
+                        println!("Lost the connection to the host, trying to get back in");
+                        *lost = Some(Reconnect::Waiting(Instant::now()));
+                        continue;
+                    }
+                };
+                match NetMessage::parse(&line, size) {
+                    Ok(NetMessage::Move(action)) => {
+                        let player = PlayerId::from(shared.rules.game().current_player());
+                        match shared.rules.apply(action, &mut shared.next_state) {
+                            Ok(()) => {
+                                println!("{player:?}: {action}");
+                                shared.log.record(action);
+                            }
+                            Err(e) => println!("The host played {action}, which does not fit this board: {e}"),
+                        }
+                        shared.board_changed.send(BoardChanged);
+                    }
+                    Ok(NetMessage::Sync(game)) => shared.follow(*game),
+                    Ok(NetMessage::Forfeit(seat)) => shared.rules.forfeit(seat, &mut shared.next_state),
+                    Ok(NetMessage::Clock(times)) => match clock.as_deref_mut() {
+                        Some(clock) => clock.set_clock(times),
+                        None => {
+                            let seen = shared.rules.game().history().len();
+                            commands.insert_resource(GameClock::new(times, seen));
+                        }
+                    },
+                    Ok(NetMessage::Reject(reason)) => println!("The host refused {reason}"),
+                    Ok(_) => {}
+                    Err(e) => println!("Ignored a message from the host: {e}"),
+                }
+            }
+            // Anything asked while the connection is down is dropped; the host's game is picked up on the way back.
+            if lost.is_some() {
+                outbox.messages.clear();
+            }
+            for message in outbox.messages.drain(..) {
+                if let Err(e) = client.send(&message) {
+                    println!("Could not reach the host: {e}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use bevy::state::app::StatesPlugin;
+    use wall_rules::{Action, Position};
+
+    /// An app running nothing but `run_network` over `link`, starting from `game`.
+    fn network_app(link: NetLink, game: Game) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<GameState>()
+            .add_event::<BoardChanged>()
+            .init_resource::<ActionLog>()
+            .init_resource::<NetOutbox>()
+            .insert_resource(GameRules::from_game(game))
+            .insert_resource(link)
+            .add_systems(Update, run_network);
+        app
+    }
+    fn host_app(grace: Duration) -> (App, u16) {
+        let host = Host::bind(0).unwrap();
+        let port = host.port();
+        let link = NetLink::Host {
+            host,
+            seats: HashMap::new(),
+            sessions: HashMap::new(),
+            away: HashMap::new(),
+            grace,
+        };
+        (network_app(link, Game::default()), port)
+    }
+    /// Joins the host at `port` while it keeps running, the way `--join` would.
+    fn join(host: &mut App, port: u16) -> (Client, Player, SessionToken, Game) {
+        let joining = thread::spawn(move || Client::join(("127.0.0.1", port)));
+        while !joining.is_finished() {
+            host.update();
+            thread::sleep(Duration::from_millis(5));
+        }
+        joining.join().unwrap().unwrap()
+    }
+    /// Updates every app until `done` holds, failing if that takes too long.
+    fn run_until(apps: &mut [&mut App], mut done: impl FnMut(&mut [&mut App]) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done(apps) {
+            assert!(Instant::now() < deadline, "gave up waiting");
+            for app in apps.iter_mut() {
+                app.update();
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+    fn game(app: &App) -> &Game {
+        app.world().resource::<GameRules>().game()
+    }
+    /// Plays `action` for the seat at this app, the way the `Referee` does.
+    fn play(app: &mut App, action: Action) {
+        let world = app.world_mut();
+        if world.resource::<NetLink>().is_host() {
+            world.resource_scope(|world, mut rules: Mut<GameRules>| {
+                rules.apply(action, &mut world.resource_mut::<NextState<GameState>>()).unwrap();
+            });
+            world.resource_mut::<ActionLog>().record(action);
+        }
+        world.resource_mut::<NetOutbox>().push(NetMessage::Move(action));
+    }
+
+    #[test]
+    fn dropped_players_rejoin_and_catch_up() {
+        let (mut host, port) = host_app(DEFAULT_GRACE);
+        let (client, seat, token, joined) = join(&mut host, port);
+        assert_eq!(seat, Player::B);
+        let link = NetLink::Client {
+            client,
+            seat: Some(seat),
+            address: format!("127.0.0.1:{port}"),
+            token: Some(token),
+            lost: None,
+        };
+        let mut player = network_app(link, joined);
+
+        play(&mut host, Action::Move(Position::new(4, 1)));
+        run_until(&mut [&mut host, &mut player], |apps| game(apps[1]).history().len() == 1);
+        play(&mut player, Action::Move(Position::new(4, 7)));
+        run_until(&mut [&mut host, &mut player], |apps| game(apps[0]).history().len() == 2);
+
+        // The host loses the player's connection and plays on without them.
+        let NetLink::Host { host: net, seats, .. } = &mut *host.world_mut().resource_mut::<NetLink>() else {
+            unreachable!();
+        };
+        let peer = seats.iter().find(|(_, taken)| **taken == seat).map(|(peer, _)| *peer).unwrap();
+        net.disconnect(peer);
+        play(&mut host, Action::Move(Position::new(4, 2)));
+
+        run_until(&mut [&mut host, &mut player], |apps| {
+            let back = matches!(apps[1].world().resource::<NetLink>(), NetLink::Client { lost: None, .. });
+            back && game(apps[1]) == game(apps[0])
+        });
+        assert_eq!(game(&player).history().len(), 3);
+        assert_eq!(game(&player).current_player(), Player::B);
+        let NetLink::Host { away, seats, .. } = host.world().resource::<NetLink>() else {
+            unreachable!();
+        };
+        assert!(away.is_empty());
+        assert!(seats.values().any(|taken| *taken == seat));
+    }
+
+    #[test]
+    fn players_who_stay_away_forfeit() {
+        let (mut host, port) = host_app(Duration::from_millis(200));
+        let (client, _, _, _) = join(&mut host, port);
+        let watching = thread::spawn(move || Client::watch(("127.0.0.1", port)));
+        while !watching.is_finished() {
+            host.update();
+        }
+        let (mut watcher, _) = watching.join().unwrap().unwrap();
+
+        drop(client);
+        run_until(&mut [&mut host], |apps| apps[0].world().resource::<GameRules>().forfeited().is_some());
+        assert_eq!(host.world().resource::<GameRules>().forfeited(), Some(Player::B));
+        assert_eq!(host.world().resource::<GameRules>().winner(), Some(Player::A));
+
+        let mut heard = Vec::new();
+        run_until(&mut [&mut host], |_| {
+            heard.extend(watcher.poll());
+            !heard.is_empty()
+        });
+        let forfeit = NetMessage::Forfeit(Player::B).to_string();
+        assert_eq!(heard, [NetEvent::Line(Client::HOST, forfeit)]);
+    }
+}
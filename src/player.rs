@@ -1,6 +1,7 @@
 use avian3d::prelude::{Collider, RigidBody};
 
 use super::*;
+use move_hints::{end_move_hints, start_move_hints};
 #[derive(Bundle, Debug)]
 pub struct MyPlayerBundle {
     my_player: MyPlayer,
@@ -40,6 +41,15 @@ impl MyPlayer {
     pub fn new(player_id: PlayerId, pos: GridPosition) -> Self {
         Self { player_id, pos }
     }
+    pub fn player_id(&self) -> PlayerId {
+        self.player_id
+    }
+    pub fn pos(&self) -> GridPosition {
+        self.pos
+    }
+    pub fn set_pos(&mut self, pos: GridPosition) {
+        self.pos = pos;
+    }
 }
 
 fn spawn_player(
@@ -48,6 +58,7 @@ fn spawn_player(
     meshes: &mut ResMut<Assets<Mesh>>,
     player:MyPlayer,
     color: Color,
+    board: &BoardSize,
     ){
     let sphere_radius = TILE_WIDTH / 3f32;
     let body = RigidBody::Kinematic;
@@ -59,11 +70,13 @@ fn spawn_player(
             perceptual_roughness: 1.0,
             ..default()
         })),
-        Transform::from_translation(player.pos.into()),
+        Transform::from_translation(board.translation(player.pos)),
         Collider::sphere(sphere_radius),
         body
     ))
+    .observe(start_move_hints)
     .observe(drag_with_collision)
+    .observe(end_move_hints)
     .observe(snap_drop_tile);
 }
 
@@ -71,20 +84,30 @@ pub fn spawn_player_bundle(
     commands: &mut Commands,
     materials: &mut ResMut<Assets<StandardMaterial>>,
     meshes: &mut ResMut<Assets<Mesh>>,
-    player: PlayerId
+    player: PlayerId,
+    pos: GridPosition,
+    board: &BoardSize,
 ){
     match player{
         PlayerId::A => {
-            let pos = GridPosition::new(2, 0);
             let player = MyPlayer::new(PlayerId::A, pos);
             let color = Color::srgb(0.0, 0.0, 1.0);
-            spawn_player(commands, materials, meshes, player, color);
+            spawn_player(commands, materials, meshes, player, color, board);
         },
         PlayerId::B => {
-            let pos = GridPosition::new(2, (N_TILES - 1) as usize);
             let player = MyPlayer::new(PlayerId::B, pos);
             let color = Color::srgb(1.0, 0.0, 0.0);
-            spawn_player(commands, materials, meshes, player, color);
+            spawn_player(commands, materials, meshes, player, color, board);
+        },
+        PlayerId::C => {
+            let player = MyPlayer::new(PlayerId::C, pos);
+            let color = Color::srgb(0.0, 1.0, 0.0);
+            spawn_player(commands, materials, meshes, player, color, board);
+        },
+        PlayerId::D => {
+            let player = MyPlayer::new(PlayerId::D, pos);
+            let color = Color::srgb(1.0, 1.0, 0.0);
+            spawn_player(commands, materials, meshes, player, color, board);
         },
     }
 
@@ -1,4 +1,5 @@
 use super::*;
+use wall_rules::{DEFAULT_BOARD_SIZE, Position};
 
 #[derive(Debug, Component, Clone, Copy, Eq, PartialEq)]
 pub struct GridPosition {
@@ -6,44 +7,94 @@ pub struct GridPosition {
     y: usize,
 }
 impl GridPosition {
-    fn min_pos() -> f32 {
-        ((N_TILES - 1) as f32 * STEP_SIZE) / 2f32
-    }
     pub fn new(x: usize, y: usize) -> Self {
-        let x = ((N_TILES - 1) as usize).min(x);
-        let y = ((N_TILES - 1) as usize).min(y);
         Self { x, y }
     }
-    fn to_usize(v: f32) -> usize {
-        let v = v + Self::min_pos();
+    pub fn as_tuple(&self)->(usize,usize){
+        (self.x,self.y)
+    }
+}
+
+/// Board sizes offered on the command line: quick, standard, long and very long games.
+pub const BOARD_SIZES: [usize; 4] = [5, 9, 11, 13];
+/// Command-line flag that picks one of the `BOARD_SIZES`.
+pub const SIZE_FLAG: &str = "--size";
+
+/// How many squares there are along each side of the board, chosen before the game starts.
+///
+/// The board is centred on the origin, so every conversion between a `GridPosition`
+/// and a point in the world goes through here.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardSize {
+    tiles: usize,
+}
+impl Default for BoardSize {
+    fn default() -> Self {
+        Self::new(DEFAULT_BOARD_SIZE)
+    }
+}
+impl BoardSize {
+    pub fn new(tiles: usize) -> Self {
+        Self { tiles }
+    }
+    /// The size picked with `--size`, if it is one of the `BOARD_SIZES`.
+    pub fn from_args() -> Option<Self> {
+        let text = flag_value(SIZE_FLAG)?;
+        match text.parse() {
+            Ok(tiles) if BOARD_SIZES.contains(&tiles) => Some(Self::new(tiles)),
+            _ => {
+                println!("{SIZE_FLAG} has to be one of {BOARD_SIZES:?}, not `{text}`");
+                None
+            }
+        }
+    }
+    pub fn tiles(&self) -> usize {
+        self.tiles
+    }
+    /// Distance from one edge of the board to the other.
+    pub fn width(&self) -> f32 {
+        self.tiles as f32 * STEP_SIZE
+    }
+    fn min_pos(&self) -> f32 {
+        ((self.tiles - 1) as f32 * STEP_SIZE) / 2f32
+    }
+    fn index_at(&self, v: f32) -> usize {
+        let v = v + self.min_pos();
         let v = (v - 1f32) / STEP_SIZE;
         let v = v.round();
-        v as usize
+        (v as usize).min(self.tiles - 1)
     }
-    fn to_float(v: usize) -> f32 {
+    fn offset_of(&self, v: usize) -> f32 {
         let v = v as f32 * STEP_SIZE;
 
-        v - Self::min_pos()
+        v - self.min_pos()
     }
-    pub fn as_tuple(&self)->(usize,usize){
-        (self.x,self.y)
+    /// The square under `translation`, or the nearest one if it is off the board.
+    pub fn grid_position(&self, translation: Vec3) -> GridPosition {
+        GridPosition::new(self.index_at(translation.x), self.index_at(translation.y))
+    }
+    /// The centre of the square at `pos`.
+    pub fn translation(&self, pos: GridPosition) -> Vec3 {
+        Vec3::new(self.offset_of(pos.x), self.offset_of(pos.y), 0.0)
     }
-    pub fn center()->GridPosition{
-        let v = N_TILES as usize / 2;
-        Self::new(v, v)
+    pub fn center(&self) -> GridPosition {
+        let v = self.tiles / 2;
+        GridPosition::new(v, v)
+    }
+}
+/// Written the way the notation names squares, e.g. `e2`.
+impl std::fmt::Display for GridPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Position::from(*self))
     }
 }
-impl From<Vec3> for GridPosition {
-    fn from(value: Vec3) -> GridPosition {
-        let x = GridPosition::to_usize(value.x);
-        let y = GridPosition::to_usize(value.y);
-        Self::new(x, y)
+impl From<GridPosition> for Position {
+    fn from(value: GridPosition) -> Position {
+        Position::new(value.x, value.y)
     }
 }
-impl From<GridPosition> for Vec3 {
-    fn from(value: GridPosition) -> Vec3 {
-        let x = GridPosition::to_float(value.x);
-        let y = GridPosition::to_float(value.y);
-        Vec3::new(x, y, 0.0)
+impl From<Position> for GridPosition {
+    fn from(value: Position) -> GridPosition {
+        Self::new(value.x, value.y)
     }
 }
@@ -0,0 +1,228 @@
+use std::fs;
+
+use super::*;
+use walls::{pile_translation, placed_wall_transform};
+use wall_rules::{Game, GameRecord, Player};
+
+/// Command-line flag that opens a game record instead of starting a new game.
+pub const REPLAY_FLAG: &str = "--replay";
+pub const STEP_FORWARD_KEY: KeyCode = KeyCode::ArrowRight;
+pub const STEP_BACK_KEY: KeyCode = KeyCode::ArrowLeft;
+const GLIDE_SECONDS: f32 = 0.35;
+
+/// The record being watched and how many of its actions are on the board.
+#[derive(Resource, Debug)]
+pub struct Replay {
+    record: GameRecord,
+    ply: usize,
+}
+impl Replay {
+    /// Reads the record at `path`, checking every move in it before anything is shown.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let record: GameRecord = text.parse().map_err(|e| format!("{e}"))?;
+        record.game().map_err(|e| e.to_string())?;
+        Ok(Self { record, ply: 0 })
+    }
+    /// The position the record starts from, before any of its moves.
+    pub fn start(&self) -> Game {
+        self.record
+            .game_after(0)
+            .expect("the record was checked when it was loaded")
+    }
+    /// The replay named by `--replay <path>` on the command line, if there is one and it can be read.
+    pub fn from_args() -> Option<Self> {
+        let path = flag_value(REPLAY_FLAG)?;
+        match Self::load(&path) {
+            Ok(replay) => Some(replay),
+            Err(e) => {
+                println!("Could not replay {path}: {e}");
+                None
+            }
+        }
+    }
+    fn status(&self) -> String {
+        format!(
+            "Replay: move {} of {}  ({:?} to step back, {:?} to step forward)",
+            self.ply,
+            self.record.actions().len(),
+            STEP_BACK_KEY,
+            STEP_FORWARD_KEY,
+        )
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct ReplayHud;
+
+/// Puts the starting position of the record on the board.
+pub fn start_replay(
+    mut commands: Commands,
+    replay: Res<Replay>,
+    mut rules: ResMut<GameRules>,
+    mut board_changed: EventWriter<BoardChanged>,
+) {
+    let game = replay
+        .record
+        .game_after(replay.ply)
+        .expect("the record was checked when it was loaded");
+    rules.set_position(game);
+    board_changed.send(BoardChanged);
+    commands.spawn((
+        ReplayHud,
+        Text::new(replay.status()),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..default()
+        },
+    ));
+}
+
+/// Slides a piece from one transform to another over a short time.
+#[derive(Debug, Component)]
+pub struct Glide {
+    from: Transform,
+    to: Transform,
+    timer: Timer,
+}
+impl Glide {
+    pub fn new(from: Transform, to: Transform) -> Self {
+        Self {
+            from,
+            to,
+            timer: Timer::from_seconds(GLIDE_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+pub fn glide_pieces(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut glide_query: Query<(Entity, &mut Transform, &mut Glide)>,
+) {
+    for (entity, mut transform, mut glide) in glide_query.iter_mut() {
+        glide.timer.tick(time.delta());
+        let t = glide.timer.fraction();
+        transform.translation = glide.from.translation.lerp(glide.to.translation, t);
+        transform.rotation = glide.from.rotation.slerp(glide.to.rotation, t);
+        if glide.timer.finished() {
+            commands.entity(entity).remove::<Glide>();
+        }
+    }
+}
+
+type ReplayPawns<'w, 's> = Query<'w, 's, (Entity, &'static Transform, &'static mut MyPlayer)>;
+type ReplayWalls<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static mut Wall,
+        &'static mut WireFrame,
+        Option<&'static Glide>,
+    ),
+>;
+
+/// Turns a wall to `orientation` if it is not facing that way already.
+fn face(wall: &mut Wall, frame: &mut WireFrame, orientation: wall_rules::Orientation) {
+    if wall.orientation() != orientation {
+        wall.rotate();
+        frame.rotate();
+    }
+}
+
+/// The arrow keys step through the record, one action at a time, sliding the piece that moved.
+pub fn step_replay(
+    keypress: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+    mut replay: ResMut<Replay>,
+    mut rules: ResMut<GameRules>,
+    mut pawn_query: ReplayPawns,
+    mut wall_query: ReplayWalls,
+    board: Res<BoardSize>,
+) {
+    let forward = keypress.just_pressed(STEP_FORWARD_KEY);
+    let back = keypress.just_pressed(STEP_BACK_KEY);
+    let (action, ply) = if forward && replay.ply < replay.record.actions().len() {
+        (replay.record.actions()[replay.ply], replay.ply + 1)
+    } else if back && replay.ply > 0 {
+        (replay.record.actions()[replay.ply - 1], replay.ply - 1)
+    } else {
+        return;
+    };
+    let game = replay
+        .record
+        .game_after(ply)
+        .expect("the record was checked when it was loaded");
+    // Whoever played the action has the turn again once it is taken back.
+    let player: Player = if forward {
+        rules.game().current_player()
+    } else {
+        game.current_player()
+    };
+    let owner = PlayerId::from(player);
+
+    match action {
+        Action::Move(_) => {
+            let to: GridPosition = game.pawn(player).into();
+            for (entity, transform, mut pawn) in pawn_query.iter_mut() {
+                if pawn.player_id() == owner {
+                    pawn.set_pos(to);
+                    let target = Transform::from_translation(board.translation(to));
+                    commands.entity(entity).insert(Glide::new(*transform, target));
+                }
+            }
+        }
+        Action::Wall(slot) if forward => {
+            // Take the wall off the top of the pile, judging by where each one is headed.
+            let top = wall_query
+                .iter_mut()
+                .filter(|(_, _, wall, _, _)| wall.owner() == owner && wall.slot().is_none())
+                .max_by(|(_, a, _, _, a_glide), (_, b, _, _, b_glide)| {
+                    let base = pile_translation(&board, owner, 0);
+                    let a = a_glide.map_or(a.translation, |g| g.to.translation);
+                    let b = b_glide.map_or(b.translation, |g| g.to.translation);
+                    a.distance(base).total_cmp(&b.distance(base))
+                });
+            if let Some((entity, transform, mut wall, mut frame, _)) = top {
+                wall.set_slot(Some(slot));
+                face(&mut wall, &mut frame, slot.orientation);
+                let target = placed_wall_transform(&board, slot);
+                commands
+                    .entity(entity)
+                    .insert(Glide::new(*transform, target))
+                    .remove::<IsDraggable>();
+            }
+        }
+        Action::Wall(slot) => {
+            let index = game.walls_left(player) - 1;
+            for (entity, transform, mut wall, mut frame, _) in wall_query.iter_mut() {
+                if wall.slot() == Some(slot) {
+                    wall.set_slot(None);
+                    face(&mut wall, &mut frame, wall_rules::Orientation::Horizontal);
+                    let target = Transform::from_translation(pile_translation(&board, owner, index));
+                    commands
+                        .entity(entity)
+                        .insert((Glide::new(*transform, target), IsDraggable));
+                }
+            }
+        }
+    }
+
+    replay.ply = ply;
+    rules.set_position(game);
+}
+
+pub fn update_replay_hud(replay: Res<Replay>, mut hud_query: Query<&mut Text, With<ReplayHud>>) {
+    for mut text in hud_query.iter_mut() {
+        **text = replay.status();
+    }
+}
@@ -0,0 +1,66 @@
+use std::fs;
+
+use super::*;
+use pos::SIZE_FLAG;
+use wall_rules::{Game, Snapshot};
+
+/// Where Ctrl+S writes the game and Ctrl+L reads it back from.
+pub const SAVE_PATH: &str = "savegame.ron";
+pub const SAVE_KEY: KeyCode = KeyCode::KeyS;
+pub const LOAD_KEY: KeyCode = KeyCode::KeyL;
+pub const PRINT_POSITION_KEY: KeyCode = KeyCode::KeyP;
+
+fn write_save(game: &Game) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(&game.snapshot(), ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    fs::write(SAVE_PATH, text).map_err(|e| e.to_string())
+}
+
+fn read_save(board: BoardSize) -> Result<Game, String> {
+    let text = fs::read_to_string(SAVE_PATH).map_err(|e| e.to_string())?;
+    let snapshot: Snapshot = ron::from_str(&text).map_err(|e| e.to_string())?;
+    let game = Game::from_snapshot(&snapshot).map_err(|e| e.to_string())?;
+    if game.size() != board.tiles() {
+        let size = game.size();
+        return Err(format!("it was saved on a {size}x{size} board, start with `{SIZE_FLAG} {size}` to load it"));
+    }
+    Ok(game)
+}
+
+/// Ctrl+S saves the game in progress, Ctrl+L throws it away and picks the saved one back up.
+/// Ctrl+P prints the position string, ready to paste after `--position`.
+pub fn save_load(
+    keypress: Res<ButtonInput<KeyCode>>,
+    mut rules: ResMut<GameRules>,
+    mut log: ResMut<ActionLog>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut board_changed: EventWriter<BoardChanged>,
+    board: Res<BoardSize>,
+) {
+    if !keypress.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    if keypress.just_pressed(PRINT_POSITION_KEY) {
+        println!("Position: {}", rules.game().position_string());
+    } else if keypress.just_pressed(SAVE_KEY) {
+        match write_save(rules.game()) {
+            Ok(()) => println!("Saved the game to {SAVE_PATH}"),
+            Err(e) => println!("Could not save to {SAVE_PATH}: {e}"),
+        }
+    } else if keypress.just_pressed(LOAD_KEY) {
+        let game = match read_save(*board) {
+            Ok(game) => game,
+            Err(e) => {
+                println!("Could not load {SAVE_PATH}: {e}");
+                return;
+            }
+        };
+        log.clear();
+        for action in game.history() {
+            log.record(*action);
+        }
+        rules.set_game(game, &mut next_state);
+        board_changed.send(BoardChanged);
+        println!("Loaded the game from {SAVE_PATH}");
+    }
+}
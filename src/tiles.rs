@@ -1,4 +1,14 @@
 use super::*;
+
+const TILE_COLOR: Color = Color::WHITE;
+const TILE_ALPHA: f32 = 0.5;
+const DIMMED_TILE_ALPHA: f32 = 0.1;
+
+/// The colour of a board tile's surface, faded out when it is `dimmed`.
+pub fn tile_color(dimmed: bool) -> Color {
+    let alpha = if dimmed { DIMMED_TILE_ALPHA } else { TILE_ALPHA };
+    TILE_COLOR.with_alpha(alpha)
+}
 #[derive(Bundle, Debug)]
 pub struct TileBundle {
     transform: Transform,
@@ -14,12 +24,14 @@ impl TileBundle {
     pub fn new(
         grid_position: GridPosition,
         grid_type: GridType,
+        board: &BoardSize,
         materials: &mut ResMut<Assets<StandardMaterial>>,
         meshes: &mut ResMut<Assets<Mesh>>,
     ) -> Option<Self> {
         match grid_type {
             GridType::Tile => Some(Self::new_tile(
                 grid_position,
+                board.translation(grid_position),
                 Vec2::splat(TILE_WIDTH),
                 materials,
                 meshes,
@@ -27,12 +39,14 @@ impl TileBundle {
             GridType::Circle => None, // todo!(),
             GridType::Horizontal => Some(Self::new_horizontal(
                 grid_position,
+                board.translation(grid_position),
                 Vec2::new(TILE_WIDTH - 1f32, TRENCH_WIDTH / 2f32 - 1f32),
                 materials,
                 meshes,
             )),
             GridType::Vertical => Some(Self::new_vertical(
                 grid_position,
+                board.translation(grid_position),
                 Vec2::new(TRENCH_WIDTH / 2f32 - 1f32, TILE_WIDTH - 1f32),
                 materials,
                 meshes,
@@ -41,6 +55,7 @@ impl TileBundle {
     }
 
     fn new_square(
+        grid_position: GridPosition,
         position: Vec3,
         x: f32,
         y: f32,
@@ -61,7 +76,7 @@ impl TileBundle {
         let material = MeshMaterial3d(materials.add(tile_color.with_alpha(alpha)));
         Self {
             transform,
-            pos: position.into(),
+            pos: grid_position,
             wire_frame_gizmo,
             mesh_3d,
             material,
@@ -72,18 +87,20 @@ impl TileBundle {
     }
     fn new_tile(
         grid_position: GridPosition,
+        translation: Vec3,
         size: Vec2,
         materials: &mut ResMut<Assets<StandardMaterial>>,
         meshes: &mut ResMut<Assets<Mesh>>,
     ) -> Self {
-        let position = Vec3::from(grid_position);
+        let position = translation;
         let x = size.x / 2f32;
         let y = size.y / 2f32;
         let frame_color = BLUE.into();
-        let tile_color = Color::WHITE;
-        let alpha = 0.5;
+        let tile_color = TILE_COLOR;
+        let alpha = TILE_ALPHA;
 
         Self::new_square(
+            grid_position,
             position,
             x,
             y,
@@ -98,53 +115,63 @@ impl TileBundle {
 
     fn new_horizontal(
         grid_position: GridPosition,
+        translation: Vec3,
         size: Vec2,
         materials: &mut ResMut<Assets<StandardMaterial>>,
         meshes: &mut ResMut<Assets<Mesh>>,
     ) -> TileBundle {
-        let position = Vec3::from(grid_position) + Vec3::ZERO.with_y(STEP_SIZE / 2f32);
+        let position = translation + Vec3::ZERO.with_y(STEP_SIZE / 2f32);
         let x = TILE_WIDTH / 2f32;
         let y = TRENCH_WIDTH / 2f32;
         let frame_color = GREEN.into();
         let tile_color = Color::BLACK;
         let alpha = 0.25;
 
-        Self::new_square(
-            position,
-            x,
-            y,
-            size,
-            frame_color,
-            tile_color,
-            alpha,
-            materials,
-            meshes,
-        )
+        Self {
+            grid_type: GridType::Horizontal,
+            ..Self::new_square(
+                grid_position,
+                position,
+                x,
+                y,
+                size,
+                frame_color,
+                tile_color,
+                alpha,
+                materials,
+                meshes,
+            )
+        }
     }
 
     fn new_vertical(
         grid_position: GridPosition,
+        translation: Vec3,
         size: Vec2,
         materials: &mut ResMut<Assets<StandardMaterial>>,
         meshes: &mut ResMut<Assets<Mesh>>,
     ) -> TileBundle {
-        let position = Vec3::from(grid_position) + Vec3::ZERO.with_x(STEP_SIZE / 2f32);
+        let position = translation + Vec3::ZERO.with_x(STEP_SIZE / 2f32);
         let x = TRENCH_WIDTH / 2f32;
         let y = TILE_WIDTH / 2f32;
         let frame_color = GREEN.into();
         let tile_color = Color::BLACK;
         let alpha = 0.15;
 
-        Self::new_square(
-            position,
-            x,
-            y,
-            size,
-            frame_color,
-            tile_color,
-            alpha,
-            materials,
-            meshes,
-        )
+        Self {
+            grid_type: GridType::Vertical,
+            ..Self::new_square(
+                grid_position,
+                position,
+                x,
+                y,
+                size,
+                frame_color,
+                tile_color,
+                alpha,
+                materials,
+                meshes,
+            )
+        }
     }
 }
@@ -0,0 +1,75 @@
+use super::*;
+use wall_rules::Action;
+
+/// A see-through wall showing where the dragged wall would land, and whether it may.
+#[derive(Debug, Component)]
+pub struct WallGhost {
+    legal: Handle<StandardMaterial>,
+    illegal: Handle<StandardMaterial>,
+}
+
+fn ghost_material(color: Srgba) -> StandardMaterial {
+    StandardMaterial {
+        base_color: Color::from(color).with_alpha(0.4),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    }
+}
+
+pub fn spawn_wall_ghost(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let legal = materials.add(ghost_material(LIME));
+    let illegal = materials.add(ghost_material(RED));
+    commands.spawn((
+        Mesh3d(meshes.add(wall_shape())),
+        MeshMaterial3d(legal.clone()),
+        Transform::default(),
+        Visibility::Hidden,
+        PickingBehavior::IGNORE,
+        WallGhost { legal, illegal },
+    ));
+}
+
+type DraggedWalls<'w, 's> =
+    Query<'w, 's, (&'static Transform, &'static Wall), (With<DragOrigin>, Without<WallGhost>)>;
+
+/// Moves the ghost to the trench slot under the dragged wall and tints it by whether the rules allow it there.
+pub fn update_wall_ghost(
+    dragged_query: DraggedWalls,
+    mut ghost_query: Query<(
+        &mut Transform,
+        &mut Visibility,
+        &mut MeshMaterial3d<StandardMaterial>,
+        &WallGhost,
+    )>,
+    rules: Res<GameRules>,
+    control: LocalControl,
+    board: Res<BoardSize>,
+) {
+    let Ok((mut transform, mut visibility, mut material, ghost)) = ghost_query.get_single_mut()
+    else {
+        return;
+    };
+    let dragged = dragged_query
+        .iter()
+        .find(|(_, wall)| control.active_player() == Some(wall.owner()));
+    let Some((wall_transform, wall)) = dragged else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    let slot = WallPosition::new(wall_slot(&board, wall_transform.translation).into(), wall.orientation());
+    let handle = match rules.game().validate(Action::Wall(slot)) {
+        Ok(()) => &ghost.legal,
+        Err(_) => &ghost.illegal,
+    };
+    if material.0 != *handle {
+        material.0 = handle.clone();
+    }
+    transform.translation = wall_slot_translation(&board, slot.pos.into());
+    transform.rotation = wall_transform.rotation;
+    *visibility = Visibility::Visible;
+}
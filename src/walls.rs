@@ -1,6 +1,9 @@
 use avian3d::prelude::{Collider, RigidBody};
 
 use super::*;
+use wall_rules::{Mode, Orientation, WallPosition};
+
+pub const WALL_COLOR: Color = Color::srgba(0.824, 0.412, 0.118, 1.0);
 #[derive(Debug, Bundle)]
 pub struct WallBundle {
     mesh: Mesh3d,
@@ -19,7 +22,10 @@ impl WallBundle {
         transform: Transform,
         wall: Wall,
     ) -> Self {
-        let frame = WireFrame::new_square(Vec2::new(wall.length, wall.width), WHITE.into());
+        let mut frame = WireFrame::new_square(Vec2::new(wall.length, wall.width), WHITE.into());
+        if wall.orientation == Orientation::Vertical {
+            frame.rotate();
+        }
         let collider = Collider::cuboid(wall.length, wall.width, WALL_HEIGHT);
         let body = RigidBody::Kinematic;
         Self {
@@ -40,58 +46,239 @@ impl WallBundle {
 pub struct Wall {
     length: f32,
     width: f32,
+    owner: PlayerId,
+    orientation: Orientation,
+    slot: Option<WallPosition>,
 }
 impl Wall {
-    pub fn new(length: f32, width: f32) -> Self {
-        Self { length, width }
+    pub fn new(length: f32, width: f32, owner: PlayerId) -> Self {
+        Self {
+            length,
+            width,
+            owner,
+            orientation: Orientation::Horizontal,
+            slot: None,
+        }
+    }
+    /// A full-size wall for `owner`'s pile.
+    pub fn new_for(owner: PlayerId) -> Self {
+        Self::new(TILE_WIDTH * 2.0, TRENCH_WIDTH, owner)
+    }
+    pub fn owner(&self) -> PlayerId {
+        self.owner
+    }
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+    pub fn rotate(&mut self) {
+        self.orientation = self.orientation.rotated();
+    }
+    /// The trench slot this wall is snapped into, if it has been placed on the board.
+    pub fn slot(&self) -> Option<WallPosition> {
+        self.slot
+    }
+    pub fn set_slot(&mut self, slot: Option<WallPosition>) {
+        self.slot = slot;
     }
 }
 impl Default for Wall {
     fn default() -> Self {
-        Self {
-            length: TILE_WIDTH * 2.0,
-            width: TRENCH_WIDTH,
-        }
+        Self::new_for(PlayerId::A)
     }
 }
 #[derive(Debug, Component, Default)]
 pub struct IsWall;
 
-pub fn spawn_wall(
-    start_pos: Vec3,
+/// Distance between neighbouring walls in a pile.
+const PILE_SPACING: f32 = TRENCH_WIDTH + TILE_WIDTH / 4f32;
+/// Gap between the edge of the board and the piles beside it, the same on every board size.
+const PILE_MARGIN: f32 = 3.0 * STEP_SIZE;
+
+/// Where `owner`'s pile starts and the step from one wall in it to the next.
+/// A and B stack theirs on the right of the board, in from their own ends. C and D start level with
+/// their own starting squares, on their own sides: C's goes down the left, D's up the right between A's and B's.
+fn pile_layout(board: &BoardSize, owner: PlayerId) -> (Vec3, Vec3) {
+    let x = board.width() / 2.0 + PILE_MARGIN;
+    let edge = board.translation(GridPosition::new(0, 0)).y;
+    let middle = board.translation(GridPosition::new(0, board.tiles() / 2)).y;
+    match owner {
+        PlayerId::A => (Vec3::new(x, edge, 0.0), Vec3::Y * PILE_SPACING),
+        PlayerId::B => (Vec3::new(x, -edge, 0.0), Vec3::NEG_Y * PILE_SPACING),
+        PlayerId::C => (Vec3::new(-x, middle, 0.0), Vec3::NEG_Y * PILE_SPACING),
+        PlayerId::D => (Vec3::new(x, middle, 0.0), Vec3::Y * PILE_SPACING),
+    }
+}
+
+/// The most walls each pile beside `board` holds in `mode` before running into the next one.
+/// Two players' piles share the right side half each; with four, D's sits in the middle of it, leaving each a quarter.
+pub fn pile_room(board: &BoardSize, mode: Mode) -> usize {
+    let side = (board.tiles() - 1) as f32 * STEP_SIZE;
+    (side / (mode.players().len() as f32 * PILE_SPACING)) as usize
+}
+
+/// Where the `index`th wall of `owner`'s pile lies, counting from the bottom of the stack.
+pub fn pile_translation(board: &BoardSize, owner: PlayerId, index: usize) -> Vec3 {
+    let (start_pos, step) = pile_layout(board, owner);
+    start_pos + step * index as f32
+}
+
+/// Lays out `owner`'s walls in a stack beside the board, starting level with their own edge.
+pub fn spawn_wall_pile(
+    owner: PlayerId,
     n_walls: usize,
+    board: &BoardSize,
     commands: &mut Commands,
     materials: &mut ResMut<Assets<StandardMaterial>>,
     meshes: &mut ResMut<Assets<Mesh>>,
 ) {
+    let (start_pos, step) = pile_layout(board, owner);
+    spawn_wall(start_pos, step, n_walls, owner, commands, materials, meshes);
+}
+
+/// The block every wall is built from, centred on the wall's transform.
+pub fn wall_shape() -> Cuboid {
     let half_length = TILE_WIDTH;
     let half_width = TRENCH_WIDTH / 2f32;
+    Cuboid::from_corners(
+        Vec3::new(-half_length, -half_width, 0.0),
+        Vec3::new(half_length + TRENCH_WIDTH, half_width, 1.0) + Vec3::Z * WALL_HEIGHT,
+    )
+}
+
+pub fn spawn_wall(
+    start_pos: Vec3,
+    step: Vec3,
+    n_walls: usize,
+    owner: PlayerId,
+    commands: &mut Commands,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+) {
     let range_width = 0..1;
     let range_height = 0..n_walls;
     for (x, y) in range_width.flat_map(|x| range_height.clone().map(move |y| (x, y))) {
-        let pos_modifier: Vec3 = Vec3::new(
-            (x) as f32 * (TILE_WIDTH + 1.0),
-            (y) as f32 * (TRENCH_WIDTH + 1.0),
-            0.0,
-        );
+        let pos_modifier: Vec3 =
+            Vec3::new((x) as f32 * (TILE_WIDTH + 1.0), 0.0, 0.0) + step * (y as f32);
         let pos = start_pos + pos_modifier;
-        let shape = Cuboid::from_corners(
-            pos - Vec3::new(half_length, half_width, 0.0),
-            pos + Vec3::new(half_length + TRENCH_WIDTH, half_width, 1.0) + Vec3::Z * WALL_HEIGHT,
-        );
-
-        let mesh = Mesh3d(meshes.add(shape));
-        let material = MeshMaterial3d(materials.add(Color::srgba(0.824, 0.412, 0.118, 1.0)));
         let transform = Transform::from_translation(pos);
-        let wall = Wall::new(2.0 * half_length, 2.0 * half_width);
-        let bundle = WallBundle::new(mesh, material, transform, wall);
-        commands
-            .spawn(bundle)
-            .observe(tag_visible_on_hover)
-            .observe(tag_invisible_on_hover_end)
-            .observe(drag)
-            .observe(snap_drop_wall)
-            .observe(rotate_hovered_wall)
-            ;
+        spawn_one_wall(Wall::new_for(owner), transform, commands, materials, meshes);
+    }
+}
+
+/// Spawns a wall that is already standing in `slot`, so it can no longer be picked up.
+pub fn spawn_placed_wall(
+    owner: PlayerId,
+    slot: WallPosition,
+    board: &BoardSize,
+    commands: &mut Commands,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+) {
+    let mut wall = Wall::new_for(owner);
+    wall.orientation = slot.orientation;
+    wall.slot = Some(slot);
+    let transform = placed_wall_transform(board, slot);
+    let entity = spawn_one_wall(wall, transform, commands, materials, meshes);
+    commands.entity(entity).remove::<IsDraggable>();
+}
+
+/// How a wall standing in `slot` sits on the board.
+pub fn placed_wall_transform(board: &BoardSize, slot: WallPosition) -> Transform {
+    let mut transform = Transform::from_translation(wall_slot_translation(board, slot.pos.into()));
+    if slot.orientation == Orientation::Vertical {
+        transform.rotate(Quat::from_axis_angle(Vec3::Z, PI / 2f32));
+    }
+    transform
+}
+
+fn spawn_one_wall(
+    wall: Wall,
+    transform: Transform,
+    commands: &mut Commands,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+) -> Entity {
+    let mesh = Mesh3d(meshes.add(wall_shape()));
+    let material = MeshMaterial3d(materials.add(WALL_COLOR));
+    let bundle = WallBundle::new(mesh, material, transform, wall);
+    commands
+        .spawn(bundle)
+        .observe(tag_visible_on_hover)
+        .observe(tag_invisible_on_hover_end)
+        .observe(remember_drag_origin)
+        .observe(drag)
+        .observe(snap_drop_wall)
+        .observe(rotate_hovered_wall)
+        .id()
+}
+
+/// Where a wall was when it was picked up, so an illegal drop can put it back.
+/// Only present while a wall that can still be placed is being dragged.
+#[derive(Debug, Component, Clone, Copy)]
+pub struct DragOrigin {
+    transform: Transform,
+    orientation: Orientation,
+}
+
+pub fn remember_drag_origin(
+    hit: Trigger<Pointer<DragStart>>,
+    mut commands: Commands,
+    wall_query: Query<(&Transform, &Wall), With<IsDraggable>>,
+) {
+    if hit.button != PointerButton::Primary {
+        return;
+    }
+    if let Ok((transform, wall)) = wall_query.get(hit.target) {
+        commands.entity(hit.target).insert(DragOrigin {
+            transform: *transform,
+            orientation: wall.orientation,
+        });
+    }
+}
+
+/// Puts a wall back where it was picked up, turning it back if it was rotated mid-drag.
+pub fn return_to_origin(
+    origin: &DragOrigin,
+    transform: &mut Transform,
+    wall: &mut Wall,
+    wireframe: &mut WireFrame,
+) {
+    *transform = origin.transform;
+    if wall.orientation != origin.orientation {
+        wall.rotate();
+        wireframe.rotate();
+    }
+}
+
+/// Tints a wall red for a moment after it was dropped somewhere illegal.
+#[derive(Debug, Component)]
+pub struct RejectionFlash {
+    timer: Timer,
+}
+impl Default for RejectionFlash {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(0.6, TimerMode::Once),
+        }
+    }
+}
+
+pub fn fade_rejection_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut flash_query: Query<(Entity, &MeshMaterial3d<StandardMaterial>, &mut RejectionFlash)>,
+) {
+    for (entity, material, mut flash) in flash_query.iter_mut() {
+        flash.timer.tick(time.delta());
+        let Some(material) = materials.get_mut(&material.0) else {
+            continue;
+        };
+        if flash.timer.finished() {
+            material.base_color = WALL_COLOR;
+            commands.entity(entity).remove::<RejectionFlash>();
+        } else {
+            material.base_color = Color::from(RED).mix(&WALL_COLOR, flash.timer.fraction());
+        }
     }
 }
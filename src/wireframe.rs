@@ -88,13 +88,25 @@ impl Default for WireFrameGizmo {
 }
 
 impl WireFrameGizmo {
-    pub fn draw(&self, point: Vec3, gizmos: &mut Gizmos) {
+    pub fn draw<Config: GizmoConfigGroup>(&self, point: Vec3, gizmos: &mut Gizmos<Config>) {
+        let color = match self {
+            WireFrameGizmo::Circle(circle_gizmo) => circle_gizmo.color,
+            WireFrameGizmo::Square(square_gizmo) => square_gizmo.color,
+        };
+        self.draw_colored(point, color, gizmos);
+    }
+    pub fn draw_colored<Config: GizmoConfigGroup>(
+        &self,
+        point: Vec3,
+        color: Color,
+        gizmos: &mut Gizmos<Config>,
+    ) {
         match self {
             WireFrameGizmo::Circle(circle_gizmo) => {
-                gizmos.circle(point, circle_gizmo.radius, circle_gizmo.color);
+                gizmos.circle(point, circle_gizmo.radius, color);
             }
             WireFrameGizmo::Square(square_gizmo) => {
-                gizmos.rect(point, square_gizmo.size, square_gizmo.color);
+                gizmos.rect(point, square_gizmo.size, color);
             }
         }
     }
@@ -114,9 +126,18 @@ impl WireFrame {
         let frame = SquareGizmo::new(size, color).into();
         Self { frame }
     }
-    pub fn draw(&self, point: Vec3, gizmos: &mut Gizmos) {
+    pub fn draw<Config: GizmoConfigGroup>(&self, point: Vec3, gizmos: &mut Gizmos<Config>) {
         self.frame.draw(point, gizmos);
     }
+    /// Draws the frame in `color` instead of its own.
+    pub fn draw_colored<Config: GizmoConfigGroup>(
+        &self,
+        point: Vec3,
+        color: Color,
+        gizmos: &mut Gizmos<Config>,
+    ) {
+        self.frame.draw_colored(point, color, gizmos);
+    }
     pub fn rotate(&mut self) {
         self.frame = match self.frame {
             WireFrameGizmo::Circle(circle) => WireFrameGizmo::Circle(circle),
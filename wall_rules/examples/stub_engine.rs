@@ -0,0 +1,18 @@
+//! The simplest engine that speaks the protocol: it walks its pawn along the shortest path and never places a wall.
+//!
+//! Build it with `cargo build -p wall_rules --example stub_engine` and hand it to the game with
+//! `--engine target/debug/examples/stub_engine`.
+use std::io;
+
+use wall_rules::{Action, serve};
+
+fn main() -> io::Result<()> {
+    serve(io::stdin().lock(), io::stdout().lock(), |game, _movetime| {
+        let player = game.current_player();
+        let goal = player.goal(game.size());
+        game.legal_pawn_moves(player)
+            .into_iter()
+            .min_by_key(|to| game.board().distance_to_goal(*to, goal).unwrap_or(usize::MAX))
+            .map(Action::Move)
+    })
+}
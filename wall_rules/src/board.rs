@@ -0,0 +1,227 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{Cardinality, GameError, Goal, Orientation, Position, TrenchSegment, WallPosition};
+
+/// The square grid and the walls placed on it. Pawns are tracked by `Game`.
+///
+/// Besides the list of walls, the board keeps track of which trench segments and crossing points are taken,
+/// since that is what decides whether another wall fits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+    size: usize,
+    walls: Vec<WallPosition>,
+    segments: HashSet<TrenchSegment>,
+    crossings: HashSet<Position>,
+}
+impl Board {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            walls: Vec::new(),
+            segments: HashSet::new(),
+            crossings: HashSet::new(),
+        }
+    }
+    pub fn size(&self) -> usize {
+        self.size
+    }
+    pub fn walls(&self) -> &[WallPosition] {
+        &self.walls
+    }
+    pub fn contains(&self, pos: Position) -> bool {
+        pos.x < self.size && pos.y < self.size
+    }
+    pub fn is_segment_taken(&self, segment: TrenchSegment) -> bool {
+        self.segments.contains(&segment)
+    }
+    pub fn is_crossing_taken(&self, crossing: Position) -> bool {
+        self.crossings.contains(&crossing)
+    }
+    /// Whether a wall stands between `from` and its neighbour in `cardinality`.
+    pub fn is_blocked(&self, from: Position, cardinality: Cardinality) -> bool {
+        let (x, y) = from.as_tuple();
+        let segment = match cardinality {
+            Cardinality::North => TrenchSegment::new(from, Orientation::Horizontal),
+            Cardinality::East => TrenchSegment::new(from, Orientation::Vertical),
+            Cardinality::South if y > 0 => {
+                TrenchSegment::new(Position::new(x, y - 1), Orientation::Horizontal)
+            }
+            Cardinality::West if x > 0 => {
+                TrenchSegment::new(Position::new(x - 1, y), Orientation::Vertical)
+            }
+            _ => return false,
+        };
+        self.is_segment_taken(segment)
+    }
+    /// Whether `wall` has room on the board: inside the edges, with its trench segments and crossing point free.
+    pub fn check_wall(&self, wall: WallPosition) -> Result<(), GameError> {
+        if !wall.fits(self.size) {
+            return Err(GameError::OffBoard);
+        }
+        if wall.segments().iter().any(|s| self.is_segment_taken(*s)) {
+            return Err(GameError::WallOverlaps);
+        }
+        if self.is_crossing_taken(wall.crossing()) {
+            return Err(GameError::WallCrosses);
+        }
+        Ok(())
+    }
+    /// The neighbouring square in `cardinality`, unless a wall or the board edge is in the way.
+    pub fn step(&self, from: Position, cardinality: Cardinality) -> Option<Position> {
+        if self.is_blocked(from, cardinality) {
+            return None;
+        }
+        from.step(cardinality, self.size)
+    }
+    /// Every square reachable from `from` in a single step.
+    pub fn neighbours(&self, from: Position) -> impl Iterator<Item = Position> + '_ {
+        Cardinality::all()
+            .into_iter()
+            .filter_map(move |cardinality| self.step(from, cardinality))
+    }
+    /// Puts `wall` on the board without checking any rules; see `Game::validate` for that.
+    pub fn add_wall(&mut self, wall: WallPosition) {
+        self.walls.push(wall);
+        self.segments.extend(wall.segments());
+        self.crossings.insert(wall.crossing());
+    }
+    pub fn remove_wall(&mut self, wall: WallPosition) -> bool {
+        let Some(index) = self.walls.iter().position(|w| *w == wall) else {
+            return false;
+        };
+        self.walls.remove(index);
+        for segment in wall.segments() {
+            self.segments.remove(&segment);
+        }
+        self.crossings.remove(&wall.crossing());
+        true
+    }
+    /// All the rules for putting `wall` down: it has to have room and every pawn in `pawns` must keep a path to its goal.
+    pub fn check_placement(
+        &self,
+        wall: WallPosition,
+        pawns: &[(Position, Goal)],
+    ) -> Result<(), GameError> {
+        self.check_wall(wall)?;
+        if !self.keeps_paths_open(wall, pawns) {
+            return Err(GameError::WallBlocksPath);
+        }
+        Ok(())
+    }
+    /// Whether every pawn in `pawns` could still reach its goal after `wall` is placed.
+    pub fn keeps_paths_open(&self, wall: WallPosition, pawns: &[(Position, Goal)]) -> bool {
+        let mut board = self.clone();
+        board.add_wall(wall);
+        pawns
+            .iter()
+            .all(|(pos, goal)| board.check_for_path(*pos, *goal))
+    }
+    /// Fewest steps from `from` to any square of `goal`, walking around walls. `None` if it is sealed off.
+    pub fn distance_to_goal(&self, from: Position, goal: Goal) -> Option<usize> {
+        self.shortest_path(from, goal).map(|path| path.len() - 1)
+    }
+    /// Whether `goal` can still be reached from `from`.
+    pub fn check_for_path(&self, from: Position, goal: Goal) -> bool {
+        self.shortest_path(from, goal).is_some()
+    }
+    /// Breadth first search from `from` to `goal`. The path includes both ends.
+    pub fn shortest_path(&self, from: Position, goal: Goal) -> Option<Vec<Position>> {
+        if !self.contains(from) {
+            return None;
+        }
+        let index = |pos: Position| pos.y * self.size + pos.x;
+        let mut previous: Vec<Option<Position>> = vec![None; self.size * self.size];
+        let mut visited = vec![false; self.size * self.size];
+        let mut queue = VecDeque::from([from]);
+        visited[index(from)] = true;
+        while let Some(current) = queue.pop_front() {
+            if goal.is_reached(current) {
+                let mut path = vec![current];
+                let mut cursor = current;
+                while let Some(prev) = previous[index(cursor)] {
+                    path.push(prev);
+                    cursor = prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.neighbours(current) {
+                if !visited[index(next)] {
+                    visited[index(next)] = true;
+                    previous[index(next)] = Some(current);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(crate::DEFAULT_BOARD_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(x: usize, y: usize, orientation: Orientation) -> WallPosition {
+        WallPosition::new(Position::new(x, y), orientation)
+    }
+
+    #[test]
+    fn walls_must_fit_on_the_board() {
+        let board = Board::new(9);
+        assert_eq!(board.check_wall(wall(7, 7, Orientation::Horizontal)), Ok(()));
+        assert_eq!(board.check_wall(wall(8, 0, Orientation::Horizontal)), Err(GameError::OffBoard));
+        assert_eq!(board.check_wall(wall(0, 8, Orientation::Vertical)), Err(GameError::OffBoard));
+    }
+
+    #[test]
+    fn walls_may_not_overlap() {
+        let mut board = Board::new(9);
+        board.add_wall(wall(3, 3, Orientation::Horizontal));
+        assert_eq!(board.check_wall(wall(3, 3, Orientation::Horizontal)), Err(GameError::WallOverlaps));
+        assert_eq!(board.check_wall(wall(4, 3, Orientation::Horizontal)), Err(GameError::WallOverlaps));
+        assert_eq!(board.check_wall(wall(2, 3, Orientation::Horizontal)), Err(GameError::WallOverlaps));
+        assert_eq!(board.check_wall(wall(5, 3, Orientation::Horizontal)), Ok(()));
+        assert_eq!(board.check_wall(wall(3, 4, Orientation::Horizontal)), Ok(()));
+    }
+
+    #[test]
+    fn walls_may_not_cross() {
+        let mut board = Board::new(9);
+        board.add_wall(wall(3, 3, Orientation::Horizontal));
+        assert_eq!(board.check_wall(wall(3, 3, Orientation::Vertical)), Err(GameError::WallCrosses));
+        // Sharing an end is fine, only the midpoint is exclusive.
+        assert_eq!(board.check_wall(wall(4, 3, Orientation::Vertical)), Ok(()));
+    }
+
+    #[test]
+    fn walls_block_the_squares_they_separate() {
+        let mut board = Board::new(9);
+        board.add_wall(wall(3, 3, Orientation::Horizontal));
+        assert_eq!(board.step(Position::new(3, 3), Cardinality::North), None);
+        assert_eq!(board.step(Position::new(4, 4), Cardinality::South), None);
+        assert_eq!(board.step(Position::new(5, 3), Cardinality::North), Some(Position::new(5, 4)));
+        assert!(board.remove_wall(wall(3, 3, Orientation::Horizontal)));
+        assert_eq!(board.step(Position::new(3, 3), Cardinality::North), Some(Position::new(3, 4)));
+    }
+
+    #[test]
+    fn walls_may_not_seal_a_pawn_off() {
+        // A lid over the bottom left corner; the pawn has to go around it to the east.
+        let mut board = Board::new(5);
+        board.add_wall(wall(0, 1, Orientation::Horizontal));
+        let start = Position::new(0, 0);
+        let pawns = [(start, Goal::Row(4))];
+        assert_eq!(board.distance_to_goal(start, Goal::Row(4)), Some(6));
+        assert_eq!(board.check_placement(wall(1, 1, Orientation::Vertical), &pawns), Ok(()));
+        assert_eq!(
+            board.check_placement(wall(1, 0, Orientation::Vertical), &pawns),
+            Err(GameError::WallBlocksPath)
+        );
+        assert!(board.keeps_paths_open(wall(1, 0, Orientation::Vertical), &[]));
+    }
+}
@@ -0,0 +1,156 @@
+//! Chess clocks: every player gets a base time for the whole game, plus an increment for each action they play.
+//!
+//! The clock does not watch the time itself; whoever runs the game tells it how long the player to move has been thinking.
+//! ```
+//! use std::time::Duration;
+//! use wall_rules::{ChessClock, Player, TimeControl};
+//! let control: TimeControl = "5+3".parse().unwrap();
+//! let mut clock = ChessClock::new(control, &[Player::A, Player::B]);
+//! clock.tick(Player::A, Duration::from_secs(10));
+//! clock.add_increment(Player::A);
+//! assert_eq!(clock.left(Player::A), Duration::from_secs(293));
+//! assert!(!clock.tick(Player::B, Duration::from_secs(299)));
+//! assert!(clock.tick(Player::B, Duration::from_secs(2)));
+//! ```
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::Player;
+
+/// How much time everyone gets, written like `5+3`: five minutes each, and three more seconds per action played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{}", self.base.as_secs() / 60, self.increment.as_secs())
+    }
+}
+impl FromStr for TimeControl {
+    type Err = TimeControlError;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bad = || TimeControlError(text.to_string());
+        let (minutes, seconds) = text.split_once('+').unwrap_or((text, "0"));
+        let minutes: u64 = minutes.parse().map_err(|_| bad())?;
+        let seconds: u64 = seconds.parse().map_err(|_| bad())?;
+        if minutes == 0 {
+            return Err(bad());
+        }
+        Ok(Self {
+            base: Duration::from_secs(minutes * 60),
+            increment: Duration::from_secs(seconds),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeControlError(String);
+impl fmt::Display for TimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` is not a time control like `5+3`", self.0)
+    }
+}
+impl std::error::Error for TimeControlError {}
+
+/// The time each player has left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChessClock {
+    control: TimeControl,
+    /// Indexed by `Player::index`, one for each player in the game.
+    left: Vec<Duration>,
+}
+impl ChessClock {
+    /// Full time for each of `players`.
+    pub fn new(control: TimeControl, players: &[Player]) -> Self {
+        Self::with_left(control, vec![control.base; players.len()])
+    }
+    /// A clock partway through a game, with `left` for each player indexed by `Player::index` (A, B, C, D).
+    pub fn with_left(control: TimeControl, left: Vec<Duration>) -> Self {
+        Self { control, left }
+    }
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+    /// The time left for each player, indexed by `Player::index` (A, B, C, D).
+    pub fn times(&self) -> &[Duration] {
+        &self.left
+    }
+    pub fn left(&self, player: Player) -> Duration {
+        self.left.get(player.index()).copied().unwrap_or_default()
+    }
+    /// Whether `player` has run out of time.
+    pub fn flagged(&self, player: Player) -> bool {
+        self.left.get(player.index()).is_some_and(Duration::is_zero)
+    }
+    /// Takes `elapsed` off `player`'s time, returning whether that ran it out.
+    pub fn tick(&mut self, player: Player, elapsed: Duration) -> bool {
+        if let Some(left) = self.left.get_mut(player.index()) {
+            *left = left.saturating_sub(elapsed);
+        }
+        self.flagged(player)
+    }
+    /// Gives `player` the increment for the action it just played.
+    pub fn add_increment(&mut self, player: Player) {
+        if let Some(left) = self.left.get_mut(player.index()) {
+            *left += self.control.increment;
+        }
+    }
+    /// Everyone back to the full base time, for a new game.
+    pub fn restart(&mut self) {
+        self.left.fill(self.control.base);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn five_plus_three() -> TimeControl {
+        "5+3".parse().unwrap()
+    }
+
+    #[test]
+    fn time_controls_round_trip() {
+        let control = five_plus_three();
+        assert_eq!(control.base, Duration::from_secs(300));
+        assert_eq!(control.increment, Duration::from_secs(3));
+        assert_eq!(control.to_string(), "5+3");
+        assert_eq!(control.to_string().parse(), Ok(control));
+        assert_eq!("10".parse::<TimeControl>().unwrap().to_string(), "10+0");
+        for bad in ["", "0+5", "5+", "+3", "5+3+1", "five+3", "5-3"] {
+            assert_eq!(bad.parse::<TimeControl>(), Err(TimeControlError(bad.to_string())), "{bad}");
+        }
+    }
+
+    #[test]
+    fn increments_go_to_the_player_who_moved() {
+        let mut clock = ChessClock::new(five_plus_three(), &[Player::A, Player::B, Player::C, Player::D]);
+        clock.tick(Player::C, Duration::from_secs(20));
+        clock.add_increment(Player::C);
+        clock.add_increment(Player::D);
+        assert_eq!(clock.left(Player::A), Duration::from_secs(300));
+        assert_eq!(clock.left(Player::C), Duration::from_secs(283));
+        assert_eq!(clock.left(Player::D), Duration::from_secs(303));
+        // The times are kept by `Player::index`, not in the order the players take their turns.
+        let times = [300, 300, 283, 303].map(Duration::from_secs);
+        assert_eq!(clock.times(), times);
+        assert_eq!(ChessClock::with_left(five_plus_three(), times.to_vec()), clock);
+
+        clock.restart();
+        assert_eq!(clock.times(), [Duration::from_secs(300); 4]);
+    }
+
+    #[test]
+    fn clocks_fall_at_zero_and_stay_there() {
+        let mut clock = ChessClock::new(five_plus_three(), &[Player::A, Player::B]);
+        assert!(!clock.tick(Player::A, Duration::from_secs(299)));
+        assert!(!clock.flagged(Player::A));
+        assert!(clock.tick(Player::A, Duration::from_secs(5)));
+        assert!(clock.flagged(Player::A));
+        assert_eq!(clock.left(Player::A), Duration::ZERO);
+        assert!(!clock.flagged(Player::B));
+        // Players not in the game have no time to run out of.
+        assert!(!clock.tick(Player::C, Duration::from_secs(1000)));
+    }
+}
@@ -0,0 +1,150 @@
+//! The game's side of the engine protocol: starting an engine program and asking it for moves.
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command as Process, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{Action, Command, Game, ProtocolError, Reply};
+
+/// How long an engine gets to answer `isready`, once it has started and again before every move.
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Extra time on top of the move time before an engine is judged to have overstepped it,
+/// to cover starting to think and writing the answer back.
+pub const MOVE_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub enum EngineError {
+    Spawn(io::Error),
+    Io(io::Error),
+    /// The engine exited or closed its output.
+    Closed,
+    /// The engine did not answer in time.
+    Timeout,
+    BadReply(ProtocolError),
+    /// The engine answered `bestmove none` although the game is still going.
+    NoMove,
+}
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Spawn(e) => write!(f, "the engine could not be started: {e}"),
+            EngineError::Io(e) => write!(f, "could not talk to the engine: {e}"),
+            EngineError::Closed => write!(f, "the engine has exited"),
+            EngineError::Timeout => write!(f, "the engine did not answer in time"),
+            EngineError::BadReply(e) => write!(f, "the engine sent something unexpected: {e}"),
+            EngineError::NoMove => write!(f, "the engine had no move to play"),
+        }
+    }
+}
+impl std::error::Error for EngineError {}
+impl From<io::Error> for EngineError {
+    fn from(e: io::Error) -> Self {
+        EngineError::Io(e)
+    }
+}
+
+/// An engine program running alongside the game, spoken to through its standard input and output.
+///
+/// Its output is read on a thread of its own, so a silent engine can be given up on once its time is up.
+#[derive(Debug)]
+pub struct ExternalEngine {
+    path: String,
+    child: Child,
+    input: ChildStdin,
+    lines: Receiver<String>,
+}
+impl ExternalEngine {
+    /// Starts the program at `path` and waits until it says it is ready.
+    pub fn spawn(path: &str) -> Result<Self, EngineError> {
+        let mut child = Process::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(EngineError::Spawn)?;
+        let input = child.stdin.take().expect("stdin was piped");
+        let output = child.stdout.take().expect("stdout was piped");
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(output).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine = Self {
+            path: path.to_string(),
+            child,
+            input,
+            lines,
+        };
+        engine.sync()?;
+        Ok(engine)
+    }
+    /// The program this engine was started from.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    /// Asks for the action to play in `game`, allowing `movetime` to think.
+    ///
+    /// The action is not checked against the rules; that is up to whoever plays it.
+    pub fn best_action(&mut self, game: &Game, movetime: Duration) -> Result<Action, EngineError> {
+        // Anything left over from a move the engine answered too late comes before the `readyok`.
+        self.sync()?;
+        self.send(&Command::Position(Box::new(game.clone())))?;
+        self.send(&Command::Go { movetime })?;
+        let deadline = Instant::now() + movetime + MOVE_GRACE;
+        loop {
+            match self.next_reply(deadline, game.size())? {
+                Reply::BestMove(Some(action)) => return Ok(action),
+                Reply::BestMove(None) => return Err(EngineError::NoMove),
+                Reply::ReadyOk | Reply::Info(_) => {}
+            }
+        }
+    }
+    fn send(&mut self, command: &Command) -> Result<(), EngineError> {
+        writeln!(self.input, "{command}")?;
+        self.input.flush()?;
+        Ok(())
+    }
+    /// Sends `isready` and throws away everything the engine says until it answers `readyok`.
+    fn sync(&mut self) -> Result<(), EngineError> {
+        self.send(&Command::IsReady)?;
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            let line = self.next_line(deadline)?;
+            // `readyok` reads the same on any board, so the size does not matter here.
+            if Reply::parse(&line, 0) == Ok(Reply::ReadyOk) {
+                return Ok(());
+            }
+        }
+    }
+    /// The next reply the protocol knows, skipping any other lines.
+    fn next_reply(&self, deadline: Instant, size: usize) -> Result<Reply, EngineError> {
+        loop {
+            let line = self.next_line(deadline)?;
+            match Reply::parse(&line, size) {
+                Ok(reply) => return Ok(reply),
+                Err(ProtocolError::Unknown(_)) => continue,
+                Err(e) => return Err(EngineError::BadReply(e)),
+            }
+        }
+    }
+    fn next_line(&self, deadline: Instant) -> Result<String, EngineError> {
+        let left = deadline.saturating_duration_since(Instant::now());
+        self.lines.recv_timeout(left).map_err(|e| match e {
+            RecvTimeoutError::Timeout => EngineError::Timeout,
+            RecvTimeoutError::Disconnected => EngineError::Closed,
+        })
+    }
+}
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send(&Command::Quit);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,478 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, Cardinality, Goal, Mode, Player, Position, WallPosition};
+
+/// One turn's worth of play: either move your pawn or place one of your walls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Move(Position),
+    Wall(WallPosition),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameError {
+    GameOver,
+    OffBoard,
+    IllegalMove,
+    NoWallsLeft,
+    WallOverlaps,
+    WallCrosses,
+    WallBlocksPath,
+    WrongPlayerCount,
+}
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            GameError::GameOver => "the game is already over",
+            GameError::OffBoard => "that position is outside the board",
+            GameError::IllegalMove => "the pawn cannot move there",
+            GameError::NoWallsLeft => "no walls left to place",
+            GameError::WallOverlaps => "another wall is already in that trench",
+            GameError::WallCrosses => "that wall would cross another wall",
+            GameError::WallBlocksPath => "that wall would cut a pawn off from its goal",
+            GameError::WrongPlayerCount => "a game needs two or four players, each with one pawn and one wall count",
+        };
+        write!(f, "{msg}")
+    }
+}
+impl std::error::Error for GameError {}
+
+/// The full state of a game in progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    mode: Mode,
+    board: Board,
+    pawns: Vec<Position>,
+    walls_left: Vec<usize>,
+    walls_per_player: usize,
+    turn: Player,
+    winner: Option<Player>,
+    history: Vec<Action>,
+    /// Who played each action in `history` and where their pawn stood before it, for taking it back.
+    played: Vec<(Player, Position)>,
+    /// Every wall on the board in the order it went down, including any the game was set up with.
+    placed: Vec<(Player, WallPosition)>,
+}
+impl Game {
+    /// A two-player game from the usual start.
+    pub fn new(size: usize, walls_per_player: usize) -> Self {
+        Self::with_mode(Mode::TwoPlayer, size, walls_per_player)
+    }
+    pub fn with_mode(mode: Mode, size: usize, walls_per_player: usize) -> Self {
+        let players = mode.players();
+        Self {
+            mode,
+            board: Board::new(size),
+            pawns: players.iter().map(|p| p.start(size)).collect(),
+            walls_left: vec![walls_per_player; players.len()],
+            walls_per_player,
+            turn: Player::A,
+            winner: None,
+            history: Vec::new(),
+            played: Vec::new(),
+            placed: Vec::new(),
+        }
+    }
+    /// A game set up in an arbitrary position rather than the usual start, with no history.
+    ///
+    /// `pawns` and `walls_left` are given in player order, and how many there are picks the mode.
+    /// The walls each player has placed plus the ones they have left make up their allowance.
+    pub fn from_position(
+        size: usize,
+        pawns: &[Position],
+        walls: &[(Player, WallPosition)],
+        walls_left: &[usize],
+        turn: Player,
+    ) -> Result<Self, GameError> {
+        let mode = Mode::for_player_count(pawns.len()).ok_or(GameError::WrongPlayerCount)?;
+        let players = mode.players();
+        let seated = |player: &Player| players.contains(player);
+        if walls_left.len() != players.len()
+            || !seated(&turn)
+            || !walls.iter().all(|(owner, _)| seated(owner))
+        {
+            return Err(GameError::WrongPlayerCount);
+        }
+        let mut board = Board::new(size);
+        for (i, pawn) in pawns.iter().enumerate() {
+            if !board.contains(*pawn) {
+                return Err(GameError::OffBoard);
+            }
+            if pawns[..i].contains(pawn) {
+                return Err(GameError::IllegalMove);
+            }
+        }
+        for (_, wall) in walls {
+            board.check_wall(*wall)?;
+            board.add_wall(*wall);
+        }
+        let walls_per_player = players
+            .iter()
+            .map(|player| {
+                let placed = walls.iter().filter(|(owner, _)| owner == player).count();
+                placed + walls_left[player.index()]
+            })
+            .max()
+            .unwrap_or_default();
+        let mut game = Self {
+            mode,
+            board,
+            pawns: pawns.to_vec(),
+            walls_left: walls_left.to_vec(),
+            walls_per_player,
+            turn,
+            winner: None,
+            history: Vec::new(),
+            played: Vec::new(),
+            placed: walls.to_vec(),
+        };
+        if !players.iter().all(|player| game.check_for_path(*player)) {
+            return Err(GameError::WallBlocksPath);
+        }
+        game.winner = game.check_for_winner();
+        Ok(game)
+    }
+    /// A new game with `actions` played in order.
+    pub fn replay(
+        size: usize,
+        walls_per_player: usize,
+        actions: &[Action],
+    ) -> Result<Self, GameError> {
+        let mut game = Self::new(size, walls_per_player);
+        for action in actions {
+            game.apply(*action)?;
+        }
+        Ok(game)
+    }
+    /// Takes back the last action, returning it.
+    pub fn undo(&mut self) -> Option<Action> {
+        let last = self.history.pop()?;
+        let (player, pawn) = self
+            .played
+            .pop()
+            .expect("every action in the history has a player");
+        match last {
+            Action::Move(_) => self.pawns[player.index()] = pawn,
+            Action::Wall(wall) => {
+                self.board.remove_wall(wall);
+                self.placed.pop();
+                self.walls_left[player.index()] += 1;
+            }
+        }
+        self.turn = player;
+        self.winner = None;
+        Some(last)
+    }
+    /// The position this game started from, before any of its history was played.
+    pub fn start(&self) -> Self {
+        let mut start = self.clone();
+        while start.undo().is_some() {}
+        start
+    }
+    /// Whether this game began from the usual start rather than a set-up position.
+    pub fn has_standard_start(&self) -> bool {
+        self.start() == Self::with_mode(self.mode, self.size(), self.walls_per_player)
+    }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    /// The players taking part, in index order.
+    pub fn players(&self) -> &'static [Player] {
+        self.mode.players()
+    }
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+    pub fn size(&self) -> usize {
+        self.board.size()
+    }
+    pub fn pawn(&self, player: Player) -> Position {
+        self.pawns[player.index()]
+    }
+    pub fn walls_left(&self, player: Player) -> usize {
+        self.walls_left[player.index()]
+    }
+    pub fn walls_per_player(&self) -> usize {
+        self.walls_per_player
+    }
+    pub fn current_player(&self) -> Player {
+        self.turn
+    }
+    pub fn winner(&self) -> Option<Player> {
+        self.winner
+    }
+    pub fn is_over(&self) -> bool {
+        self.winner.is_some()
+    }
+    /// Every action applied so far, oldest first.
+    pub fn history(&self) -> &[Action] {
+        &self.history
+    }
+    /// Who played the `turn`th action of the history, counting from zero.
+    pub fn player_for_turn(&self, turn: usize) -> Option<Player> {
+        self.played.get(turn).map(|(player, _)| *player)
+    }
+    /// How many turns `player` has played so far.
+    pub fn turns_taken(&self, player: Player) -> usize {
+        self.played.iter().filter(|(p, _)| *p == player).count()
+    }
+    /// Every wall on the board together with the player who placed it.
+    pub fn placed_walls(&self) -> Vec<(Player, WallPosition)> {
+        self.placed.clone()
+    }
+
+    /// Squares `player`'s pawn may move to from where it stands now.
+    ///
+    /// A pawn steps one square in any direction a wall does not block. If another pawn is in the way,
+    /// it jumps straight over it instead, and if a wall or the board edge stops that jump,
+    /// it may step diagonally to either side of the pawn it is facing.
+    pub fn legal_pawn_moves(&self, player: Player) -> Vec<Position> {
+        let from = self.pawn(player);
+        let mut moves = Vec::new();
+        for cardinality in Cardinality::all() {
+            let Some(next) = self.board.step(from, cardinality) else {
+                continue;
+            };
+            if !self.is_occupied(next) {
+                moves.push(next);
+                continue;
+            }
+            match self.board.step(next, cardinality) {
+                Some(jump) if !self.is_occupied(jump) => moves.push(jump),
+                _ => moves.extend(
+                    cardinality
+                        .sideways()
+                        .into_iter()
+                        .filter_map(|side| self.board.step(next, side))
+                        .filter(|diagonal| !self.is_occupied(*diagonal)),
+                ),
+            }
+        }
+        moves.sort();
+        moves.dedup();
+        moves
+    }
+    /// Every pawn paired with the goal it is racing towards.
+    pub fn pawn_goals(&self) -> Vec<(Position, Goal)> {
+        self.players()
+            .iter()
+            .map(|player| (self.pawn(*player), player.goal(self.size())))
+            .collect()
+    }
+    fn is_occupied(&self, pos: Position) -> bool {
+        self.pawns.contains(&pos)
+    }
+
+    /// Checks `action` for the player whose turn it is, without applying it.
+    pub fn validate(&self, action: Action) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        match action {
+            Action::Move(to) => {
+                if !self.board.contains(to) {
+                    return Err(GameError::OffBoard);
+                }
+                if !self.legal_pawn_moves(self.turn).contains(&to) {
+                    return Err(GameError::IllegalMove);
+                }
+            }
+            Action::Wall(wall) => {
+                if self.walls_left(self.turn) == 0 {
+                    return Err(GameError::NoWallsLeft);
+                }
+                self.board.check_placement(wall, &self.pawn_goals())?;
+            }
+        }
+        Ok(())
+    }
+    /// Plays `action` for the current player and passes the turn on.
+    pub fn apply(&mut self, action: Action) -> Result<(), GameError> {
+        self.validate(action)?;
+        let player = self.turn;
+        self.played.push((player, self.pawn(player)));
+        match action {
+            Action::Move(to) => self.pawns[player.index()] = to,
+            Action::Wall(wall) => {
+                self.board.add_wall(wall);
+                self.placed.push((player, wall));
+                self.walls_left[player.index()] -= 1;
+            }
+        }
+        self.history.push(action);
+        self.winner = self.check_for_winner();
+        self.turn = self.mode.next(player);
+        Ok(())
+    }
+    pub fn move_pawn(&mut self, to: Position) -> Result<(), GameError> {
+        self.apply(Action::Move(to))
+    }
+    pub fn move_player(&mut self, cardinality: Cardinality) -> Result<(), GameError> {
+        let to = self
+            .pawn(self.turn)
+            .step(cardinality, self.size())
+            .ok_or(GameError::OffBoard)?;
+        self.move_pawn(to)
+    }
+    pub fn place_wall(&mut self, wall: WallPosition) -> Result<(), GameError> {
+        self.apply(Action::Wall(wall))
+    }
+
+    /// Whether `player` can still reach their goal.
+    pub fn check_for_path(&self, player: Player) -> bool {
+        self.board
+            .check_for_path(self.pawn(player), player.goal(self.size()))
+    }
+    /// Fewest steps `player`'s pawn needs to reach their goal, walking around the walls.
+    ///
+    /// The rules never let a wall seal a pawn in, but should it happen this counts every square on the board.
+    pub fn distance_to_goal(&self, player: Player) -> usize {
+        self.board
+            .distance_to_goal(self.pawn(player), player.goal(self.size()))
+            .unwrap_or(self.size() * self.size())
+    }
+    pub fn check_for_winner(&self) -> Option<Player> {
+        self.players()
+            .iter()
+            .copied()
+            .find(|player| player.goal(self.size()).is_reached(self.pawn(*player)))
+    }
+}
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(crate::DEFAULT_BOARD_SIZE, crate::DEFAULT_WALLS_PER_PLAYER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Orientation;
+
+    fn at(x: usize, y: usize) -> Position {
+        Position::new(x, y)
+    }
+    fn wall(x: usize, y: usize, orientation: Orientation) -> WallPosition {
+        WallPosition::new(at(x, y), orientation)
+    }
+
+    #[test]
+    fn pawn_moves_pass_the_turn() {
+        let mut game = Game::default();
+        assert_eq!(game.apply(Action::Move(at(4, 1))), Ok(()));
+        assert_eq!(game.pawn(Player::A), at(4, 1));
+        assert_eq!(game.current_player(), Player::B);
+        assert_eq!(game.apply(Action::Move(at(4, 7))), Ok(()));
+        assert_eq!(game.current_player(), Player::A);
+        assert_eq!(game.history(), [Action::Move(at(4, 1)), Action::Move(at(4, 7))]);
+    }
+
+    #[test]
+    fn illegal_actions_change_nothing() {
+        let mut game = Game::default();
+        assert_eq!(game.validate(Action::Move(at(4, 2))), Err(GameError::IllegalMove));
+        assert_eq!(game.validate(Action::Move(at(9, 0))), Err(GameError::OffBoard));
+        assert_eq!(game.apply(Action::Move(at(5, 1))), Err(GameError::IllegalMove));
+        assert_eq!(game, Game::default());
+    }
+
+    #[test]
+    fn walls_use_up_the_allowance() {
+        let mut game = Game::new(9, 1);
+        assert_eq!(game.place_wall(wall(0, 0, Orientation::Horizontal)), Ok(()));
+        assert_eq!(game.walls_left(Player::A), 0);
+        assert_eq!(game.placed_walls(), [(Player::A, wall(0, 0, Orientation::Horizontal))]);
+        game.move_pawn(at(4, 7)).unwrap();
+        assert_eq!(game.validate(Action::Wall(wall(5, 5, Orientation::Horizontal))), Err(GameError::NoWallsLeft));
+        let mut game = Game::new(9, 0);
+        assert_eq!(game.place_wall(wall(5, 5, Orientation::Horizontal)), Err(GameError::NoWallsLeft));
+    }
+
+    #[test]
+    fn walls_may_not_block_any_pawn() {
+        // Player B in the top left corner, walled off to the east, so its only way out is south.
+        let walls = [(Player::A, wall(0, 7, Orientation::Vertical))];
+        let game = Game::from_position(9, &[at(4, 0), at(0, 8)], &walls, &[9, 10], Player::A).unwrap();
+        assert_eq!(
+            game.validate(Action::Wall(wall(0, 6, Orientation::Horizontal))),
+            Err(GameError::WallBlocksPath)
+        );
+        assert_eq!(
+            game.validate(Action::Wall(wall(0, 7, Orientation::Horizontal))),
+            Err(GameError::WallCrosses)
+        );
+        assert_eq!(game.validate(Action::Wall(wall(0, 5, Orientation::Horizontal))), Ok(()));
+    }
+
+    #[test]
+    fn undo_takes_back_moves_and_walls() {
+        let mut game = Game::default();
+        game.move_pawn(at(4, 1)).unwrap();
+        game.place_wall(wall(3, 1, Orientation::Horizontal)).unwrap();
+        assert_eq!(game.undo(), Some(Action::Wall(wall(3, 1, Orientation::Horizontal))));
+        assert_eq!(game.walls_left(Player::B), 10);
+        assert!(game.board().walls().is_empty());
+        assert_eq!(game.current_player(), Player::B);
+        assert_eq!(game.validate(Action::Wall(wall(3, 1, Orientation::Horizontal))), Ok(()));
+        assert_eq!(game.undo(), Some(Action::Move(at(4, 1))));
+        assert_eq!(game, Game::default());
+        assert_eq!(game.undo(), None);
+    }
+
+    #[test]
+    fn reaching_the_far_edge_wins() {
+        let mut game = Game::from_position(9, &[at(4, 7), at(0, 8)], &[], &[0, 0], Player::A).unwrap();
+        assert_eq!(game.winner(), None);
+        game.move_pawn(at(4, 8)).unwrap();
+        assert_eq!(game.winner(), Some(Player::A));
+        assert!(game.is_over());
+        assert_eq!(game.validate(Action::Move(at(0, 7))), Err(GameError::GameOver));
+        game.undo();
+        assert_eq!(game.winner(), None);
+    }
+
+    #[test]
+    fn pawns_jump_straight_over_each_other() {
+        let game = Game::from_position(9, &[at(4, 4), at(4, 5)], &[], &[10, 10], Player::A).unwrap();
+        assert_eq!(game.legal_pawn_moves(Player::A), [at(3, 4), at(4, 3), at(4, 6), at(5, 4)]);
+    }
+
+    #[test]
+    fn a_wall_behind_the_pawn_allows_diagonals() {
+        let walls = [(Player::B, wall(4, 5, Orientation::Horizontal))];
+        let game = Game::from_position(9, &[at(4, 4), at(4, 5)], &walls, &[10, 9], Player::A).unwrap();
+        assert_eq!(
+            game.legal_pawn_moves(Player::A),
+            [at(3, 4), at(3, 5), at(4, 3), at(5, 4), at(5, 5)]
+        );
+    }
+
+    #[test]
+    fn the_board_edge_behind_the_pawn_allows_diagonals() {
+        let game = Game::from_position(9, &[at(4, 7), at(4, 8)], &[], &[10, 10], Player::A).unwrap();
+        assert_eq!(
+            game.legal_pawn_moves(Player::A),
+            [at(3, 7), at(3, 8), at(4, 6), at(5, 7), at(5, 8)]
+        );
+    }
+
+    #[test]
+    fn walls_block_diagonal_moves() {
+        let walls = [
+            (Player::B, wall(3, 5, Orientation::Horizontal)),
+            (Player::B, wall(4, 5, Orientation::Vertical)),
+        ];
+        let game = Game::from_position(9, &[at(4, 4), at(4, 5)], &walls, &[10, 8], Player::A).unwrap();
+        assert_eq!(game.legal_pawn_moves(Player::A), [at(3, 4), at(3, 5), at(4, 3), at(5, 4)]);
+    }
+
+    #[test]
+    fn pawns_may_not_jump_onto_another_pawn() {
+        // Four players: C in front of A, B right behind C, and D on one of the diagonals.
+        let pawns = [at(4, 4), at(4, 6), at(4, 5), at(5, 5)];
+        let game = Game::from_position(9, &pawns, &[], &[5, 5, 5, 5], Player::A).unwrap();
+        assert_eq!(game.legal_pawn_moves(Player::A), [at(3, 4), at(3, 5), at(4, 3), at(5, 4)]);
+    }
+}
@@ -0,0 +1,53 @@
+//! The rules of the wall game, without any rendering.
+//!
+//! Everything in here is plain data: a `Board` knows where the walls are, a `Game` knows
+//! where the pawns are, how many walls each player has left, whose turn it is and who won.
+//! The Bevy front-end, bots and tools all drive the game through `Game::apply`.
+mod board;
+mod clock;
+mod external;
+mod game;
+mod mcts;
+mod net;
+mod notation;
+mod player;
+mod position;
+mod position_string;
+mod protocol;
+mod record;
+mod search;
+mod snapshot;
+
+pub use board::Board;
+pub use clock::{ChessClock, TimeControl, TimeControlError};
+pub use external::{EngineError, ExternalEngine, MOVE_GRACE, STARTUP_TIMEOUT};
+pub use game::{Action, Game, GameError};
+pub use mcts::{MctsConfig, mcts_action};
+pub use net::{
+    Client, DEFAULT_PORT, Host, JOIN_TIMEOUT, NetError, NetEvent, NetMessage, PeerId, SessionToken,
+};
+pub use notation::{NotationError, parse_action, parse_position, parse_wall};
+pub use player::{Goal, Mode, Player};
+pub use position::{Cardinality, Orientation, Position, TrenchSegment, WallPosition};
+pub use position_string::PositionStringError;
+pub use protocol::{Command, ProtocolError, Reply, serve};
+pub use record::{
+    DATE_TAG, GameRecord, PLAYERS_TAG, POSITION_TAG, RESULT_TAG, RecordError, SIZE_TAG, WALLS_TAG,
+    player_tag, result_text,
+};
+pub use search::{SearchLimit, best_action, candidate_actions};
+pub use snapshot::{Snapshot, SnapshotError};
+
+/// Side length of the standard board.
+pub const DEFAULT_BOARD_SIZE: usize = 9;
+/// Smallest and largest boards the rules take. Files are lettered, so there can be no more than 26.
+pub const MIN_BOARD_SIZE: usize = 2;
+pub const MAX_BOARD_SIZE: usize = 26;
+/// Walls each player starts with in a standard two-player game.
+pub const DEFAULT_WALLS_PER_PLAYER: usize = walls_for_board_size(DEFAULT_BOARD_SIZE);
+
+/// Walls each player starts with in a two-player game on a `size` x `size` board: one more than
+/// the side, which gives the standard ten on the standard board. See `Mode::walls_per_player`.
+pub const fn walls_for_board_size(size: usize) -> usize {
+    size + 1
+}
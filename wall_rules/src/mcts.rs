@@ -0,0 +1,311 @@
+//! A second computer opponent, using Monte Carlo tree search.
+//!
+//! Alpha-beta has to look at every wall it is offered at every level, so it only gets a few
+//! actions deep. This search instead plays many quick games from the current position, and
+//! spends more of them on the actions that keep winning. Like alpha-beta, it only considers
+//! walls across the others' shortest paths. The quick games mostly walk each pawn along its
+//! shortest path, while whoever is losing the race now and then puts a wall in the leader's
+//! way, and are cut off after a while and handed to whoever is then closest to their goal.
+//! ```
+//! use wall_rules::{Action, Game, MctsConfig, Position, mcts_action};
+//! let game = Game::from_position_string("9 e8,a9 -/- 0,0 a").unwrap();
+//! let config = MctsConfig::new(200);
+//! assert_eq!(mcts_action(&game, &config), Some(Action::Move(Position::new(4, 8))));
+//! ```
+use std::cmp::Reverse;
+
+use fastrand::Rng;
+
+use crate::{Action, Game, Player, WallPosition, candidate_actions, search::walls_across};
+
+/// How much the search favours trying actions it has played little over ones that have done well.
+const EXPLORATION: f32 = 1.4;
+/// A node only gets another child once it has been visited enough, roughly this many per square root of its visits,
+/// so the search looks deeper down the promising lines instead of trying every wall once.
+const WIDENING: f32 = 2.0;
+/// Chance that a player behind in a quick game places a wall instead of stepping along the shortest path, if they have any left.
+const ROLLOUT_WALL_CHANCE: f32 = 0.3;
+/// Chance that a quick game makes a random pawn move instead of the shortest-path one.
+const ROLLOUT_WANDER_CHANCE: f32 = 0.1;
+
+/// How much thinking the tree search does before it plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MctsConfig {
+    /// How many quick games to play out; more is stronger and slower.
+    pub playouts: usize,
+    /// The quick games are random, so the same seed always picks the same action.
+    pub seed: u64,
+}
+impl MctsConfig {
+    pub fn new(playouts: usize) -> Self {
+        Self { playouts, seed: 0 }
+    }
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+/// The action the tree search picks for the current player in `game`, or `None` if the game is over.
+pub fn mcts_action(game: &Game, config: &MctsConfig) -> Option<Action> {
+    if game.is_over() {
+        return None;
+    }
+    // Quick games from any move here might all be won, so a win on the spot would not stand out.
+    let player = game.current_player();
+    let goal = player.goal(game.size());
+    if let Some(to) = game.legal_pawn_moves(player).into_iter().find(|to| goal.is_reached(*to)) {
+        return Some(Action::Move(to));
+    }
+    let mut tree = Tree::new(game);
+    // Fresh quick games for every action of the game; the same ones each time make the same mistakes over and over.
+    let mut rng = Rng::with_seed(config.seed.wrapping_add(game.history().len() as u64));
+    for _ in 0..config.playouts.max(1) {
+        tree.playout(game, &mut rng);
+    }
+    tree.most_visited()
+}
+
+struct Node {
+    /// What was played to get here from the parent, and by whom.
+    action: Option<(Player, Action)>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Actions not tried from here yet. The most promising are at the end, since they are taken from there.
+    untried: Vec<Action>,
+    visits: u32,
+    /// Quick games won by the player who made `action`.
+    wins: f32,
+}
+
+/// Every node lives in one list and points at the others by index.
+struct Tree {
+    nodes: Vec<Node>,
+}
+impl Tree {
+    fn new(game: &Game) -> Self {
+        Self {
+            nodes: vec![Node {
+                action: None,
+                parent: None,
+                children: Vec::new(),
+                untried: untried_actions(game),
+                visits: 0,
+                wins: 0.0,
+            }],
+        }
+    }
+    /// Walks down the tree to a node with something left to try, adds one child, plays a quick game from it
+    /// and counts the result in every node on the way.
+    fn playout(&mut self, root: &Game, rng: &mut Rng) {
+        let mut game = root.clone();
+        let mut node = 0;
+        while !self.can_expand(node) && !self.nodes[node].children.is_empty() {
+            node = self.select_child(node);
+            let (_, action) = self.nodes[node].action.expect("only the root has no action");
+            game.apply(action).expect("actions in the tree were legal when they were added");
+        }
+        if let Some(child) = self.expand(node, &mut game) {
+            node = child;
+        }
+        let winner = rollout(&mut game, rng);
+        let mut cursor = Some(node);
+        while let Some(index) = cursor {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            if node.action.is_some_and(|(player, _)| player == winner) {
+                node.wins += 1.0;
+            }
+            cursor = node.parent;
+        }
+    }
+    fn can_expand(&self, index: usize) -> bool {
+        let node = &self.nodes[index];
+        let room = 1.0 + WIDENING * (node.visits as f32).sqrt();
+        !node.untried.is_empty() && (node.children.len() as f32) < room
+    }
+    /// The child with the best upper confidence bound, balancing how well it has done against how little it has been tried.
+    fn select_child(&self, parent: usize) -> usize {
+        let log_visits = (self.nodes[parent].visits.max(1) as f32).ln();
+        let score = |child: usize| {
+            let node = &self.nodes[child];
+            let visits = node.visits.max(1) as f32;
+            node.wins / visits + EXPLORATION * (log_visits / visits).sqrt()
+        };
+        *self.nodes[parent]
+            .children
+            .iter()
+            .max_by(|a, b| score(**a).total_cmp(&score(**b)))
+            .expect("only called on nodes with children")
+    }
+    /// Plays the next untried action from `parent` that turns out to be legal and adds a node for it.
+    fn expand(&mut self, parent: usize, game: &mut Game) -> Option<usize> {
+        if game.is_over() {
+            return None;
+        }
+        let player = game.current_player();
+        while let Some(action) = self.nodes[parent].untried.pop() {
+            if game.apply(action).is_err() {
+                continue;
+            }
+            let child = self.nodes.len();
+            self.nodes.push(Node {
+                action: Some((player, action)),
+                parent: Some(parent),
+                children: Vec::new(),
+                untried: untried_actions(game),
+                visits: 0,
+                wins: 0.0,
+            });
+            self.nodes[parent].children.push(child);
+            return Some(child);
+        }
+        None
+    }
+    fn most_visited(&self) -> Option<Action> {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|child| &self.nodes[*child])
+            .max_by_key(|node| node.visits)
+            .and_then(|node| node.action)
+            .map(|(_, action)| action)
+    }
+}
+
+/// The pawn moves and the walls across the others' shortest paths, ordered so the step along the player's own
+/// shortest path comes up first. Walls anywhere else are left out: there are far too many to try them all.
+///
+/// Walls are checked only once they are tried, since most of them never are.
+fn untried_actions(game: &Game) -> Vec<Action> {
+    if game.is_over() {
+        return Vec::new();
+    }
+    let player = game.current_player();
+    let goal = player.goal(game.size());
+    let mut moves = game.legal_pawn_moves(player);
+    moves.sort_by_key(|to| Reverse(game.board().distance_to_goal(*to, goal).unwrap_or(usize::MAX)));
+    // Taken from the end, so the pawn moves go last and are tried first.
+    candidate_actions(game)
+        .into_iter()
+        .filter(|action| matches!(action, Action::Wall(_)))
+        .rev()
+        .chain(moves.into_iter().map(Action::Move))
+        .collect()
+}
+
+/// Plays on from `game` until someone wins or every pawn has had time to cross the board, and says who came out ahead.
+fn rollout(game: &mut Game, rng: &mut Rng) -> Player {
+    let max_plies = game.size() * game.players().len();
+    for _ in 0..max_plies {
+        if game.is_over() {
+            break;
+        }
+        let action = rollout_action(game, rng);
+        if game.apply(action).is_err() {
+            // A random wall that did not fit; walk instead.
+            let walk = shortest_path_move(game);
+            if walk.is_none_or(|walk| game.apply(walk).is_err()) {
+                break;
+            }
+        }
+    }
+    if let Some(winner) = game.winner() {
+        return winner;
+    }
+    // Nobody got there in time: whoever has the least left to walk, with ties going to whoever moves first.
+    let mut player = game.current_player();
+    let mut best = player;
+    for _ in 0..game.players().len() {
+        if game.distance_to_goal(player) < game.distance_to_goal(best) {
+            best = player;
+        }
+        player = game.mode().next(player);
+    }
+    best
+}
+
+/// Usually a step along the shortest path. A player losing the race sometimes puts a wall across the leader's path instead,
+/// and now and then a pawn takes a random step.
+fn rollout_action(game: &Game, rng: &mut Rng) -> Action {
+    let player = game.current_player();
+    if game.walls_left(player) > 0
+        && rng.f32() < ROLLOUT_WALL_CHANCE
+        && let Some(wall) = wall_against_leader(game, rng)
+    {
+        return Action::Wall(wall);
+    }
+    let moves = game.legal_pawn_moves(player);
+    if !moves.is_empty() && rng.f32() < ROLLOUT_WANDER_CHANCE {
+        return Action::Move(moves[rng.usize(..moves.len())]);
+    }
+    shortest_path_move(game).unwrap_or(Action::Move(game.pawn(player)))
+}
+
+/// A wall across a random step of the path of whoever is closest to their goal, if that is not the player to move.
+/// It may not fit; the quick game walks instead then.
+fn wall_against_leader(game: &Game, rng: &mut Rng) -> Option<WallPosition> {
+    let player = game.current_player();
+    let board = game.board();
+    let path_of = |other: Player| board.shortest_path(game.pawn(other), other.goal(game.size()));
+    let own = path_of(player)?.len();
+    let leader = game
+        .players()
+        .iter()
+        .filter(|other| **other != player)
+        .filter_map(|other| path_of(*other))
+        .min_by_key(Vec::len)?;
+    // Level with the leader still wins the race, moving first.
+    if leader.len() >= own {
+        return None;
+    }
+    let step = rng.usize(..leader.len() - 1);
+    let walls = walls_across(leader[step], leader[step + 1]);
+    rng.choice(walls)
+}
+
+/// The pawn move that leaves the player to move closest to their goal.
+fn shortest_path_move(game: &Game) -> Option<Action> {
+    let player = game.current_player();
+    let goal = player.goal(game.size());
+    game.legal_pawn_moves(player)
+        .into_iter()
+        .min_by_key(|to| game.board().distance_to_goal(*to, goal).unwrap_or(usize::MAX))
+        .map(Action::Move)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Position, SearchLimit, best_action, walls_for_board_size};
+
+    #[test]
+    fn takes_a_win_on_the_spot() {
+        let game = Game::from_position_string("9 e8,e2 -/- 10,10 a").unwrap();
+        let config = MctsConfig::new(50);
+        assert_eq!(mcts_action(&game, &config), Some(Action::Move(Position::new(4, 8))));
+    }
+
+    /// Plays a whole game on a small board against alpha-beta looking two actions ahead, and says who won.
+    fn play_alpha_beta(me: Player, seed: u64) -> Option<Player> {
+        let mut game = Game::new(5, walls_for_board_size(5));
+        let config = MctsConfig::new(3000).with_seed(seed);
+        while !game.is_over() && game.history().len() < 100 {
+            let action = if game.current_player() == me {
+                mcts_action(&game, &config)
+            } else {
+                best_action(&game, SearchLimit::Depth(2))
+            };
+            game.apply(action.unwrap()).unwrap();
+        }
+        game.winner()
+    }
+
+    #[test]
+    fn beats_a_shallow_alpha_beta_on_a_small_board() {
+        for seed in 0..2 {
+            for me in [Player::A, Player::B] {
+                assert_eq!(play_alpha_beta(me, seed), Some(me), "playing {me:?} with seed {seed}");
+            }
+        }
+    }
+}
@@ -0,0 +1,489 @@
+//! Playing over the network: the messages the host and the players exchange, and the sockets carrying them.
+//!
+//! One instance hosts and keeps the game everyone plays; the others join it by address and each
+//! get a seat, or just watch. The host checks every move before anyone plays it. Messages are single lines of text:
+//!
+//! | Message                         | Sent by | Meaning                                                                 |
+//! |---------------------------------|---------|-------------------------------------------------------------------------|
+//! | `join`                          | player  | asks for a seat                                                         |
+//! | `welcome <seat> <token> <game>` | host    | the seat to play, the token to take it back with and the game so far    |
+//! | `full`                          | host    | every seat is taken                                                     |
+//! | `watch`                         | watcher | asks to follow the game without a seat, answered with `sync`            |
+//! | `rejoin <token> <actions>`      | player  | asks for its seat back after losing the connection, having seen this many actions |
+//! | `move <action>`                 | both    | from a player a request, from the host an action to play                |
+//! | `reject <reason>`               | host    | the requested move or rejoin was not allowed                            |
+//! | `sync <game>`                   | host    | drop the game and carry on from this one, e.g. for a rematch            |
+//! | `forfeit <seat>`                | host    | the seat lost without finishing, e.g. by staying away too long          |
+//! | `clock <control> <ms>...`       | host    | the time control and every player's time left, in milliseconds         |
+//!
+//! A player who rejoins is welcomed with the game as far as it had seen it, followed by a `move` for every action it missed.
+//! A game is written the way the engine protocol's `position` command writes it.
+//! ```
+//! use wall_rules::{Game, NetMessage, Player, SessionToken};
+//! let token = SessionToken(0x2a);
+//! let welcome = NetMessage::Welcome { seat: Player::B, token, game: Box::new(Game::default()) };
+//! assert_eq!(welcome.to_string(), "welcome b 000000000000002a 9 e1,e9 -/- 10,10 a");
+//! assert_eq!(NetMessage::parse(&welcome.to_string(), 9), Ok(welcome));
+//! ```
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    str::FromStr,
+    sync::{
+        Mutex,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Action, ChessClock, Game, Player, ProtocolError, parse_action,
+    protocol::{game_text, parse_game},
+};
+
+/// The port a host listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 7878;
+/// How long joining waits for the host to answer.
+pub const JOIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a player shows the host to get its seat back after losing the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SessionToken(pub u64);
+impl SessionToken {
+    /// A fresh token nobody could guess, drawn from the operating system's secure random numbers.
+    pub fn new() -> Self {
+        let mut bytes = [0; 8];
+        getrandom::getrandom(&mut bytes).expect("the operating system should always have random numbers to give");
+        Self(u64::from_le_bytes(bytes))
+    }
+}
+impl Default for SessionToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl fmt::Display for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+impl FromStr for SessionToken {
+    type Err = ProtocolError;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(text, 16)
+            .map(SessionToken)
+            .map_err(|_| ProtocolError::BadNumber(text.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetMessage {
+    Join,
+    Welcome {
+        seat: Player,
+        token: SessionToken,
+        game: Box<Game>,
+    },
+    Full,
+    Watch,
+    /// Asks for the seat `token` was handed out with, having seen the first `seen` actions of the game.
+    Rejoin { token: SessionToken, seen: usize },
+    Move(Action),
+    Reject(String),
+    Sync(Box<Game>),
+    Forfeit(Player),
+    Clock(ChessClock),
+}
+impl NetMessage {
+    /// Reads a message about a game on a `size` x `size` board.
+    pub fn parse(line: &str, size: usize) -> Result<Self, ProtocolError> {
+        let mut words = line.split_whitespace();
+        let missing = ProtocolError::MissingArgument;
+        match words.next() {
+            Some("join") => Ok(NetMessage::Join),
+            Some("full") => Ok(NetMessage::Full),
+            Some("watch") => Ok(NetMessage::Watch),
+            Some("welcome") => {
+                let seat = words.next().ok_or(missing("seat"))?;
+                let seat = parse_seat(seat).ok_or_else(|| ProtocolError::Unknown(seat.to_string()))?;
+                let token = words.next().ok_or(missing("session token"))?.parse()?;
+                Ok(NetMessage::Welcome {
+                    seat,
+                    token,
+                    game: Box::new(parse_game(words)?),
+                })
+            }
+            Some("rejoin") => {
+                let token = words.next().ok_or(missing("session token"))?.parse()?;
+                let seen = words.next().ok_or(missing("number of actions"))?;
+                let seen = seen.parse().map_err(|_| ProtocolError::BadNumber(seen.to_string()))?;
+                Ok(NetMessage::Rejoin { token, seen })
+            }
+            Some("forfeit") => {
+                let seat = words.next().ok_or(missing("seat"))?;
+                let seat = parse_seat(seat).ok_or_else(|| ProtocolError::Unknown(seat.to_string()))?;
+                Ok(NetMessage::Forfeit(seat))
+            }
+            Some("clock") => {
+                let control = words.next().ok_or(missing("time control"))?;
+                let control = control
+                    .parse()
+                    .map_err(|_| ProtocolError::Unknown(control.to_string()))?;
+                let left = words
+                    .map(|millis| {
+                        millis
+                            .parse()
+                            .map(Duration::from_millis)
+                            .map_err(|_| ProtocolError::BadNumber(millis.to_string()))
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(NetMessage::Clock(ChessClock::with_left(control, left)))
+            }
+            Some("move") => {
+                let action = words.next().ok_or(missing("action"))?;
+                Ok(NetMessage::Move(parse_action(action, size)?))
+            }
+            Some("reject") => Ok(NetMessage::Reject(words.collect::<Vec<_>>().join(" "))),
+            Some("sync") => Ok(NetMessage::Sync(Box::new(parse_game(words)?))),
+            _ => Err(ProtocolError::Unknown(line.to_string())),
+        }
+    }
+}
+impl fmt::Display for NetMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetMessage::Join => write!(f, "join"),
+            NetMessage::Full => write!(f, "full"),
+            NetMessage::Watch => write!(f, "watch"),
+            NetMessage::Welcome { seat, token, game } => {
+                write!(f, "welcome {} {token} {}", seat_letter(*seat), game_text(game))
+            }
+            NetMessage::Rejoin { token, seen } => write!(f, "rejoin {token} {seen}"),
+            NetMessage::Forfeit(seat) => write!(f, "forfeit {}", seat_letter(*seat)),
+            NetMessage::Clock(clock) => {
+                write!(f, "clock {}", clock.control())?;
+                clock
+                    .times()
+                    .iter()
+                    .try_for_each(|left| write!(f, " {}", left.as_millis()))
+            }
+            NetMessage::Move(action) => write!(f, "move {action}"),
+            NetMessage::Reject(reason) => write!(f, "reject {reason}"),
+            NetMessage::Sync(game) => write!(f, "sync {}", game_text(game)),
+        }
+    }
+}
+
+fn seat_letter(player: Player) -> String {
+    format!("{player:?}").to_lowercase()
+}
+
+fn parse_seat(text: &str) -> Option<Player> {
+    Player::all()
+        .into_iter()
+        .find(|player| seat_letter(*player) == text.to_lowercase())
+}
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    /// The host has no free seat.
+    Full,
+    /// The other side hung up.
+    Closed,
+    /// The host did not answer in time.
+    Timeout,
+    /// The host said no, for the reason given.
+    Refused(String),
+    BadMessage(ProtocolError),
+}
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(e) => write!(f, "{e}"),
+            NetError::Full => write!(f, "the game is full"),
+            NetError::Closed => write!(f, "the connection was closed"),
+            NetError::Timeout => write!(f, "the host did not answer in time"),
+            NetError::Refused(reason) => write!(f, "the host refused: {reason}"),
+            NetError::BadMessage(e) => write!(f, "the host sent something unexpected: {e}"),
+        }
+    }
+}
+impl std::error::Error for NetError {}
+impl From<io::Error> for NetError {
+    fn from(e: io::Error) -> Self {
+        NetError::Io(e)
+    }
+}
+
+/// Which connection a line came from. The host numbers its connections from zero as they come in.
+pub type PeerId = usize;
+
+/// Something that happened on the network since it was last looked at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetEvent {
+    Connected(PeerId),
+    /// A line of text from a peer, still to be parsed since that needs to know the board size.
+    Line(PeerId, String),
+    Closed(PeerId),
+}
+
+/// What the reader threads hand to whoever owns the socket. New connections bring along the way to write to them.
+enum Incoming {
+    Connected(PeerId, Sender<String>),
+    Event(NetEvent),
+}
+
+/// Passes every line arriving on `stream` to `sender` until the connection closes.
+fn forward_lines(peer: PeerId, stream: TcpStream, sender: Sender<Incoming>) {
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if sender.send(Incoming::Event(NetEvent::Line(peer, line))).is_err() {
+                return;
+            }
+        }
+        let _ = sender.send(Incoming::Event(NetEvent::Closed(peer)));
+    });
+}
+
+/// Writes every line handed to the returned sender to `stream`, on a thread of its own so a slow peer holds nobody up.
+/// Hangs up once the sender is dropped and everything handed to it has gone out, or as soon as a write fails.
+fn write_lines(mut stream: TcpStream) -> Sender<String> {
+    let (sender, lines) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for line in lines {
+            if writeln!(stream, "{line}").and_then(|()| stream.flush()).is_err() {
+                break;
+            }
+        }
+        // The reading thread then sees the connection close.
+        let _ = stream.shutdown(Shutdown::Both);
+    });
+    sender
+}
+
+/// The hosting side: accepts connections on a thread of its own and talks to each peer.
+///
+/// Reading from and writing to each peer happens on threads too, so sending never waits on the network.
+#[derive(Debug)]
+pub struct Host {
+    /// Where to hand the lines for each peer's writing thread.
+    peers: HashMap<PeerId, Sender<String>>,
+    /// Behind a lock only so the host can be shared between threads; nothing else reads from it.
+    incoming: Mutex<Receiver<Incoming>>,
+    port: u16,
+}
+impl Host {
+    /// Starts listening on every interface at `port`. Port `0` picks any free one; see `port`.
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let port = listener.local_addr()?.port();
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            for (peer, stream) in listener.incoming().map_while(Result::ok).enumerate() {
+                let _ = stream.set_nodelay(true);
+                let Ok(reader) = stream.try_clone() else {
+                    continue;
+                };
+                if sender.send(Incoming::Connected(peer, write_lines(stream))).is_err() {
+                    return;
+                }
+                forward_lines(peer, reader, sender.clone());
+            }
+        });
+        Ok(Self {
+            peers: HashMap::new(),
+            incoming: Mutex::new(incoming),
+            port,
+        })
+    }
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+    /// Everything that happened since the last call, without waiting.
+    pub fn poll(&mut self) -> Vec<NetEvent> {
+        let mut events = Vec::new();
+        let incoming = self.incoming.get_mut().expect("nothing panics while holding the lock");
+        while let Ok(incoming) = incoming.try_recv() {
+            match incoming {
+                Incoming::Connected(peer, outbox) => {
+                    self.peers.insert(peer, outbox);
+                    events.push(NetEvent::Connected(peer));
+                }
+                Incoming::Event(event) => {
+                    if let NetEvent::Closed(peer) = event {
+                        self.peers.remove(&peer);
+                    }
+                    events.push(event);
+                }
+            }
+        }
+        events
+    }
+    /// Sends `message` to one peer, without waiting for it to go out. A peer that cannot be written to is dropped.
+    pub fn send(&mut self, peer: PeerId, message: &NetMessage) {
+        if let Some(outbox) = self.peers.get(&peer)
+            && outbox.send(message.to_string()).is_err()
+        {
+            self.disconnect(peer);
+        }
+    }
+    /// Sends `message` to every peer.
+    pub fn broadcast(&mut self, message: &NetMessage) {
+        let peers: Vec<PeerId> = self.peers.keys().copied().collect();
+        for peer in peers {
+            self.send(peer, message);
+        }
+    }
+    /// Hangs up on `peer`, once everything already sent to it has gone out.
+    pub fn disconnect(&mut self, peer: PeerId) {
+        self.peers.remove(&peer);
+    }
+}
+
+/// The joining side: one connection, to the host, read and written on threads of its own like the host's.
+#[derive(Debug)]
+pub struct Client {
+    /// Where to hand the lines for the writing thread. Dropping the client hangs up.
+    outbox: Sender<String>,
+    /// Behind a lock only so the client can be shared between threads; nothing else reads from it.
+    incoming: Mutex<Receiver<Incoming>>,
+}
+impl Client {
+    /// The peer id events from the host carry.
+    pub const HOST: PeerId = 0;
+
+    /// Connects to the host at `address`, like `192.168.1.20:7878`, and asks for a seat.
+    /// Returns the seat handed out, the token to take it back with, and the game so far.
+    pub fn join(address: impl ToSocketAddrs) -> Result<(Self, Player, SessionToken, Game), NetError> {
+        match Self::ask(address, &NetMessage::Join)? {
+            (client, NetMessage::Welcome { seat, token, game }) => Ok((client, seat, token, *game)),
+            (_, NetMessage::Full) => Err(NetError::Full),
+            (_, answer) => Err(NetError::BadMessage(ProtocolError::Unknown(answer.to_string()))),
+        }
+    }
+    /// Connects to the host at `address` again after losing the connection, to take back the seat `token` was handed out with.
+    /// Returns the seat and the game as far as the first `seen` actions; the host sends the ones missed after that as moves.
+    pub fn rejoin(address: impl ToSocketAddrs, token: SessionToken, seen: usize) -> Result<(Self, Player, Game), NetError> {
+        match Self::ask(address, &NetMessage::Rejoin { token, seen })? {
+            (client, NetMessage::Welcome { seat, game, .. }) => Ok((client, seat, *game)),
+            (_, NetMessage::Reject(reason)) => Err(NetError::Refused(reason)),
+            (_, answer) => Err(NetError::BadMessage(ProtocolError::Unknown(answer.to_string()))),
+        }
+    }
+    /// Connects to the host at `address` to follow the game without playing in it.
+    /// Returns the game so far; every move after it arrives as it is played.
+    pub fn watch(address: impl ToSocketAddrs) -> Result<(Self, Game), NetError> {
+        match Self::ask(address, &NetMessage::Watch)? {
+            (client, NetMessage::Sync(game)) => Ok((client, *game)),
+            (_, answer) => Err(NetError::BadMessage(ProtocolError::Unknown(answer.to_string()))),
+        }
+    }
+    /// Connects to the host at `address`, sends `request` and waits for the first answer.
+    fn ask(address: impl ToSocketAddrs, request: &NetMessage) -> Result<(Self, NetMessage), NetError> {
+        let mut client = Self::connect(address)?;
+        client.send(request)?;
+        let deadline = Instant::now() + JOIN_TIMEOUT;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let incoming = client.incoming.get_mut().expect("nothing panics while holding the lock");
+            let line = match incoming.recv_timeout(left) {
+                Ok(Incoming::Event(NetEvent::Line(_, line))) => line,
+                Ok(Incoming::Event(NetEvent::Closed(_))) | Err(RecvTimeoutError::Disconnected) => {
+                    return Err(NetError::Closed);
+                }
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => return Err(NetError::Timeout),
+            };
+            // Nothing before the first answer needs the board size.
+            let answer = NetMessage::parse(&line, 0).map_err(NetError::BadMessage)?;
+            return Ok((client, answer));
+        }
+    }
+    fn connect(address: impl ToSocketAddrs) -> Result<Self, NetError> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        let (sender, incoming) = mpsc::channel();
+        forward_lines(Self::HOST, stream.try_clone()?, sender);
+        Ok(Self {
+            outbox: write_lines(stream),
+            incoming: Mutex::new(incoming),
+        })
+    }
+    /// Everything the host sent since the last call, without waiting.
+    pub fn poll(&mut self) -> Vec<NetEvent> {
+        let mut events = Vec::new();
+        let incoming = self.incoming.get_mut().expect("nothing panics while holding the lock");
+        while let Ok(Incoming::Event(event)) = incoming.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+    /// Sends `message` to the host, without waiting for it to go out.
+    /// Fails once the connection is gone.
+    pub fn send(&mut self, message: &NetMessage) -> io::Result<()> {
+        self.outbox
+            .send(message.to_string())
+            .map_err(|_| io::Error::from(io::ErrorKind::NotConnected))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    /// Polls until `poll` has something, or gives up after a while.
+    fn wait_for(mut poll: impl FnMut() -> Vec<NetEvent>) -> Vec<NetEvent> {
+        let deadline = Instant::now() + JOIN_TIMEOUT;
+        while Instant::now() < deadline {
+            let events = poll();
+            if !events.is_empty() {
+                return events;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("nothing arrived in time");
+    }
+
+    #[test]
+    fn moves_go_back_and_forth_over_localhost() {
+        let mut host = Host::bind(0).unwrap();
+        let port = host.port();
+        let joining = thread::spawn(move || Client::join(("127.0.0.1", port)).unwrap());
+
+        let mut events = Vec::new();
+        while !events.iter().any(|event| matches!(event, NetEvent::Line(..))) {
+            events.extend(wait_for(|| host.poll()));
+        }
+        let peer = match events.as_slice() {
+            [NetEvent::Connected(peer), NetEvent::Line(from, line)] if from == peer => {
+                assert_eq!(NetMessage::parse(line, 0), Ok(NetMessage::Join));
+                *peer
+            }
+            other => panic!("unexpected {other:?}"),
+        };
+        let token = SessionToken(7);
+        let game = Box::new(Game::default());
+        host.send(peer, &NetMessage::Welcome { seat: Player::B, token, game });
+
+        let (mut client, seat, welcome_token, game) = joining.join().unwrap();
+        assert_eq!((seat, welcome_token, game), (Player::B, token, Game::default()));
+
+        let action = Action::Move(Position::new(4, 1));
+        host.broadcast(&NetMessage::Move(action));
+        let line = NetMessage::Move(action).to_string();
+        assert_eq!(wait_for(|| client.poll()), [NetEvent::Line(Client::HOST, line)]);
+
+        let reply = Action::Move(Position::new(4, 7));
+        client.send(&NetMessage::Move(reply)).unwrap();
+        let line = NetMessage::Move(reply).to_string();
+        assert_eq!(wait_for(|| host.poll()), [NetEvent::Line(peer, line)]);
+
+        host.disconnect(peer);
+        assert_eq!(wait_for(|| client.poll()), [NetEvent::Closed(Client::HOST)]);
+    }
+}
@@ -0,0 +1,176 @@
+//! The community notation for writing games down.
+//!
+//! Files are letters from `a` on the left, ranks are numbers from `1` on player A's side.
+//! A pawn move is the square it lands on, like `e2`. A wall is named by the top-left square of the
+//! four it sits between, followed by `h` or `v` for its orientation, like `e3h`.
+//!
+//! Formatting and parsing round-trip:
+//! ```
+//! use wall_rules::{Action, Orientation, Position, WallPosition, parse_action};
+//! let wall = Action::Wall(WallPosition::new(Position::new(4, 1), Orientation::Horizontal));
+//! assert_eq!(wall.to_string(), "e3h");
+//! assert_eq!(parse_action("e3h", 9), Ok(wall));
+//! assert_eq!(parse_action("e2", 9), Ok(Action::Move(Position::new(4, 1))));
+//! assert!(parse_action("j1", 9).is_err());
+//! ```
+use std::fmt;
+
+use crate::{Action, Orientation, Position, WallPosition};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    Empty,
+    BadFile(char),
+    BadRank(String),
+    BadOrientation(String),
+    OffBoard(String),
+}
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Empty => write!(f, "expected a square like `e2` but got nothing"),
+            NotationError::BadFile(c) => write!(f, "`{c}` is not a file, expected a letter"),
+            NotationError::BadRank(rank) => write!(f, "`{rank}` is not a rank, expected a number from 1"),
+            NotationError::BadOrientation(rest) => {
+                write!(f, "`{rest}` is not a wall orientation, expected `h` or `v`")
+            }
+            NotationError::OffBoard(text) => write!(f, "`{text}` is outside the board"),
+        }
+    }
+}
+impl std::error::Error for NotationError {}
+
+fn file_char(x: usize) -> char {
+    (b'a' + x as u8) as char
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", file_char(self.x), self.y + 1)
+    }
+}
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Orientation::Horizontal => write!(f, "h"),
+            Orientation::Vertical => write!(f, "v"),
+        }
+    }
+}
+impl fmt::Display for WallPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The anchor is the bottom-left square, the name uses the one above it.
+        let top_left = Position::new(self.pos.x, self.pos.y + 1);
+        write!(f, "{top_left}{}", self.orientation)
+    }
+}
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Move(pos) => write!(f, "{pos}"),
+            Action::Wall(wall) => write!(f, "{wall}"),
+        }
+    }
+}
+
+/// Splits `text` into the square at its start and whatever follows the rank.
+fn parse_square(text: &str) -> Result<(Position, &str), NotationError> {
+    let mut chars = text.chars();
+    let file = chars.next().ok_or(NotationError::Empty)?;
+    if !file.is_ascii_alphabetic() {
+        return Err(NotationError::BadFile(file));
+    }
+    let x = (file.to_ascii_lowercase() as u8 - b'a') as usize;
+    let rest = chars.as_str();
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    let (rank, rest) = rest.split_at(digits);
+    let y = match rank.parse::<usize>() {
+        Ok(rank) if rank > 0 => rank - 1,
+        _ => return Err(NotationError::BadRank(rank.to_string())),
+    };
+    Ok((Position::new(x, y), rest))
+}
+
+/// Reads a square like `e2` on a `size` x `size` board.
+pub fn parse_position(text: &str, size: usize) -> Result<Position, NotationError> {
+    let text = text.trim();
+    match parse_square(text)? {
+        (pos, "") if pos.x < size && pos.y < size => Ok(pos),
+        (_, "") => Err(NotationError::OffBoard(text.to_string())),
+        (_, rest) => Err(NotationError::BadOrientation(rest.to_string())),
+    }
+}
+
+/// Reads a wall like `e3h` on a `size` x `size` board.
+pub fn parse_wall(text: &str, size: usize) -> Result<WallPosition, NotationError> {
+    let text = text.trim();
+    let (top_left, rest) = parse_square(text)?;
+    let orientation = match rest {
+        "h" | "H" => Orientation::Horizontal,
+        "v" | "V" => Orientation::Vertical,
+        _ => return Err(NotationError::BadOrientation(rest.to_string())),
+    };
+    let off_board = || NotationError::OffBoard(text.to_string());
+    let y = top_left.y.checked_sub(1).ok_or_else(off_board)?;
+    let wall = WallPosition::new(Position::new(top_left.x, y), orientation);
+    if !wall.fits(size) {
+        return Err(off_board());
+    }
+    Ok(wall)
+}
+
+/// Reads either a pawn move or a wall, telling them apart by the orientation suffix.
+pub fn parse_action(text: &str, size: usize) -> Result<Action, NotationError> {
+    let text = text.trim();
+    if text.ends_with(|c: char| c.is_ascii_alphabetic()) {
+        parse_wall(text, size).map(Action::Wall)
+    } else {
+        parse_position(text, size).map(Action::Move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_action_round_trips() {
+        for size in [5, 9, 13] {
+            for (x, y) in (0..size).flat_map(|x| (0..size).map(move |y| (x, y))) {
+                let pos = Position::new(x, y);
+                let action = Action::Move(pos);
+                assert_eq!(parse_action(&action.to_string(), size), Ok(action));
+                for orientation in [Orientation::Horizontal, Orientation::Vertical] {
+                    let wall = WallPosition::new(pos, orientation);
+                    if wall.fits(size) {
+                        let action = Action::Wall(wall);
+                        assert_eq!(parse_action(&action.to_string(), size), Ok(action));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn squares_off_the_board_are_rejected() {
+        assert_eq!(parse_action("j1", 9), Err(NotationError::OffBoard("j1".to_string())));
+        assert_eq!(parse_action("a10", 9), Err(NotationError::OffBoard("a10".to_string())));
+        assert_eq!(parse_action("a0", 9), Err(NotationError::BadRank("0".to_string())));
+        // The top-left square of a wall can be neither on the bottom rank nor in the last file.
+        assert_eq!(parse_action("a1h", 9), Err(NotationError::OffBoard("a1h".to_string())));
+        assert_eq!(parse_action("i5v", 9), Err(NotationError::OffBoard("i5v".to_string())));
+    }
+
+    #[test]
+    fn malformed_actions_are_rejected() {
+        assert_eq!(parse_action("", 9), Err(NotationError::Empty));
+        assert_eq!(parse_action("   ", 9), Err(NotationError::Empty));
+        assert_eq!(parse_action("e3x", 9), Err(NotationError::BadOrientation("x".to_string())));
+        assert_eq!(parse_action("e3hv", 9), Err(NotationError::BadOrientation("hv".to_string())));
+        assert_eq!(parse_action("e2!", 9), Err(NotationError::BadOrientation("!".to_string())));
+        assert_eq!(parse_action("3e", 9), Err(NotationError::BadFile('3')));
+        assert_eq!(parse_action("eh", 9), Err(NotationError::BadRank(String::new())));
+        assert_eq!(parse_wall("e2", 9), Err(NotationError::BadOrientation(String::new())));
+        assert_eq!(parse_position("e3h", 9), Err(NotationError::BadOrientation("h".to_string())));
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Position;
+
+/// A seat at the board. Two-player games use `A` and `B`; four-player games add `C` and `D`.
+///
+/// `A` starts at the bottom edge, `B` at the top, `C` on the left and `D` on the right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Player {
+    A,
+    B,
+    C,
+    D,
+}
+impl Player {
+    /// Every seat, in index order.
+    pub fn all() -> [Self; 4] {
+        [Self::A, Self::B, Self::C, Self::D]
+    }
+    pub fn index(&self) -> usize {
+        match self {
+            Player::A => 0,
+            Player::B => 1,
+            Player::C => 2,
+            Player::D => 3,
+        }
+    }
+    /// The player across the board.
+    pub fn opponent(&self) -> Self {
+        match self {
+            Player::A => Player::B,
+            Player::B => Player::A,
+            Player::C => Player::D,
+            Player::D => Player::C,
+        }
+    }
+    /// Pawns start in the middle of their own edge.
+    pub fn start(&self, size: usize) -> Position {
+        match self {
+            Player::A => Position::new(size / 2, 0),
+            Player::B => Position::new(size / 2, size - 1),
+            Player::C => Position::new(0, size / 2),
+            Player::D => Position::new(size - 1, size / 2),
+        }
+    }
+    /// Each pawn races towards the edge opposite of where it started.
+    pub fn goal(&self, size: usize) -> Goal {
+        match self {
+            Player::A => Goal::Row(size - 1),
+            Player::B => Goal::Row(0),
+            Player::C => Goal::Column(size - 1),
+            Player::D => Goal::Column(0),
+        }
+    }
+}
+
+/// How many players sit around the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Mode {
+    #[default]
+    TwoPlayer,
+    FourPlayer,
+}
+impl Mode {
+    /// The mode with `count` players in it, if there is one.
+    pub fn for_player_count(count: usize) -> Option<Self> {
+        match count {
+            2 => Some(Mode::TwoPlayer),
+            4 => Some(Mode::FourPlayer),
+            _ => None,
+        }
+    }
+    /// The players taking part, in index order.
+    pub fn players(&self) -> &'static [Player] {
+        match self {
+            Mode::TwoPlayer => &[Player::A, Player::B],
+            Mode::FourPlayer => &[Player::A, Player::B, Player::C, Player::D],
+        }
+    }
+    /// Who moves after `player`. Turns go clockwise around the board.
+    pub fn next(&self, player: Player) -> Player {
+        match (self, player) {
+            (Mode::TwoPlayer, _) => player.opponent(),
+            (Mode::FourPlayer, Player::A) => Player::C,
+            (Mode::FourPlayer, Player::C) => Player::B,
+            (Mode::FourPlayer, Player::B) => Player::D,
+            (Mode::FourPlayer, Player::D) => Player::A,
+        }
+    }
+    /// Walls each player starts with on a `size` x `size` board.
+    /// Everyone shares the same total, so four players get half as many as two.
+    pub fn walls_per_player(&self, size: usize) -> usize {
+        crate::walls_for_board_size(size) * 2 / self.players().len()
+    }
+}
+
+/// The squares a pawn has to reach to win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    Row(usize),
+    Column(usize),
+}
+impl Goal {
+    pub fn is_reached(&self, pos: Position) -> bool {
+        match self {
+            Goal::Row(y) => pos.y == *y,
+            Goal::Column(x) => pos.x == *x,
+        }
+    }
+    /// Fewest steps to the goal if there were no walls; a lower bound for path searches.
+    pub fn distance(&self, pos: Position) -> usize {
+        match self {
+            Goal::Row(y) => pos.y.abs_diff(*y),
+            Goal::Column(x) => pos.x.abs_diff(*x),
+        }
+    }
+}
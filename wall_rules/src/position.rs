@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+/// A square on the board. `x` is the column, `y` the row; `(0, 0)` is the corner on player A's side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Position {
+    pub x: usize,
+    pub y: usize,
+}
+impl Position {
+    pub const fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+    pub fn as_tuple(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+    /// The neighbouring square in `cardinality`, if it does not fall off the `size` x `size` board.
+    pub fn step(&self, cardinality: Cardinality, size: usize) -> Option<Position> {
+        let (dx, dy) = cardinality.offset();
+        let x = self.x.checked_add_signed(dx)?;
+        let y = self.y.checked_add_signed(dy)?;
+        (x < size && y < size).then_some(Position::new(x, y))
+    }
+    /// Number of orthogonal steps between two squares, ignoring walls.
+    pub fn distance(&self, other: Position) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+}
+
+/// The four directions a pawn can step in. North is towards higher rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cardinality {
+    North,
+    East,
+    South,
+    West,
+}
+impl Cardinality {
+    pub fn all() -> [Self; 4] {
+        [Self::North, Self::East, Self::South, Self::West]
+    }
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Cardinality::North => (0, 1),
+            Cardinality::East => (1, 0),
+            Cardinality::South => (0, -1),
+            Cardinality::West => (-1, 0),
+        }
+    }
+    /// The two directions at right angles to this one.
+    pub fn sideways(&self) -> [Self; 2] {
+        match self {
+            Cardinality::North | Cardinality::South => [Cardinality::East, Cardinality::West],
+            Cardinality::East | Cardinality::West => [Cardinality::North, Cardinality::South],
+        }
+    }
+    pub fn opposite(&self) -> Self {
+        match self {
+            Cardinality::North => Cardinality::South,
+            Cardinality::East => Cardinality::West,
+            Cardinality::South => Cardinality::North,
+            Cardinality::West => Cardinality::East,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+}
+impl Orientation {
+    pub fn rotated(&self) -> Self {
+        match self {
+            Orientation::Horizontal => Orientation::Vertical,
+            Orientation::Vertical => Orientation::Horizontal,
+        }
+    }
+}
+
+/// A wall is two tiles long and is anchored on the square with the lowest `x` and `y` it touches.
+///
+/// A horizontal wall at `(x, y)` separates rows `y` and `y + 1` for columns `x` and `x + 1`.
+/// A vertical wall at `(x, y)` separates columns `x` and `x + 1` for rows `y` and `y + 1`.
+/// Both cross the same midpoint, between the four squares `(x..=x+1, y..=y+1)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct WallPosition {
+    pub pos: Position,
+    pub orientation: Orientation,
+}
+impl WallPosition {
+    pub const fn new(pos: Position, orientation: Orientation) -> Self {
+        Self { pos, orientation }
+    }
+    /// Walls need room for their second half and may not stick out of the far edge.
+    pub fn fits(&self, size: usize) -> bool {
+        self.pos.x + 1 < size && self.pos.y + 1 < size
+    }
+    /// The two tile-long trench segments this wall fills.
+    pub fn segments(&self) -> [TrenchSegment; 2] {
+        let (x, y) = self.pos.as_tuple();
+        let second = match self.orientation {
+            Orientation::Horizontal => Position::new(x + 1, y),
+            Orientation::Vertical => Position::new(x, y + 1),
+        };
+        [
+            TrenchSegment::new(self.pos, self.orientation),
+            TrenchSegment::new(second, self.orientation),
+        ]
+    }
+    /// The point where the trenches cross in the middle of the wall. Only one wall can pass through it.
+    pub fn crossing(&self) -> Position {
+        self.pos
+    }
+}
+
+/// One tile-long piece of trench next to the square at `pos`.
+///
+/// A horizontal segment separates `pos` from the square north of it,
+/// a vertical segment separates `pos` from the square east of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TrenchSegment {
+    pub pos: Position,
+    pub orientation: Orientation,
+}
+impl TrenchSegment {
+    pub const fn new(pos: Position, orientation: Orientation) -> Self {
+        Self { pos, orientation }
+    }
+}
@@ -0,0 +1,232 @@
+//! A whole position written on one line, in the spirit of chess FEN.
+//!
+//! Five fields separated by spaces: the board size, the pawns in player order, each player's
+//! placed walls separated by `/` (`-` for none), each player's walls left, and the side to move.
+//! Four-player positions simply list four of everything.
+//! ```
+//! use wall_rules::Game;
+//! let game = Game::default();
+//! assert_eq!(game.position_string(), "9 e1,e9 -/- 10,10 a");
+//! assert_eq!(Game::from_position_string("9 e1,e9 -/- 10,10 a"), Ok(game));
+//! assert!(Game::from_position_string("9 e1,e9 e3h,e3v/- 9,10 b").is_err());
+//! assert!(Game::from_position_string("9 e1,e9,a5,i5 -/-/-/- 5,5,5,5 c").is_ok());
+//! assert!(Game::from_position_string("30 a1,a30 -/- 0,0 a").is_err());
+//! assert!(Game::from_position_string("9 e1,e9 -/-/a1h 10,10 a").is_err());
+//! ```
+use std::fmt;
+
+use crate::{Game, GameError, MAX_BOARD_SIZE, MIN_BOARD_SIZE, NotationError, Player, parse_position, parse_wall};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionStringError {
+    MissingField(&'static str),
+    ExtraField(String),
+    BadNumber(String),
+    /// Boards go from `MIN_BOARD_SIZE` to `MAX_BOARD_SIZE`, one letter per file.
+    BadSize(usize),
+    /// More `/`-separated wall lists than there are pawns.
+    ExtraWalls(String),
+    BadSide(String),
+    BadNotation(NotationError),
+    Illegal(GameError),
+}
+impl fmt::Display for PositionStringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionStringError::MissingField(field) => write!(f, "the line ended before the {field}"),
+            PositionStringError::ExtraField(field) => write!(f, "unexpected `{field}` after the side to move"),
+            PositionStringError::BadNumber(text) => write!(f, "`{text}` is not a number"),
+            PositionStringError::BadSize(size) => write!(
+                f,
+                "a board of size {size} is not supported, it has to be between {MIN_BOARD_SIZE} and {MAX_BOARD_SIZE}"
+            ),
+            PositionStringError::ExtraWalls(list) => write!(f, "the walls `{list}` belong to no pawn"),
+            PositionStringError::BadSide(text) => write!(f, "`{text}` is not a player, expected a letter like `a`"),
+            PositionStringError::BadNotation(e) => write!(f, "{e}"),
+            PositionStringError::Illegal(e) => write!(f, "the position is not legal: {e}"),
+        }
+    }
+}
+impl std::error::Error for PositionStringError {}
+impl From<NotationError> for PositionStringError {
+    fn from(e: NotationError) -> Self {
+        PositionStringError::BadNotation(e)
+    }
+}
+
+fn player_letter(player: Player) -> String {
+    format!("{player:?}").to_lowercase()
+}
+
+fn parse_number(text: &str) -> Result<usize, PositionStringError> {
+    text.parse()
+        .map_err(|_| PositionStringError::BadNumber(text.to_string()))
+}
+
+impl Game {
+    /// This position on one line. The history is left out, so undo stops here once it is read back.
+    pub fn position_string(&self) -> String {
+        let players = self.players();
+        let pawns: Vec<String> = players.iter().map(|p| self.pawn(*p).to_string()).collect();
+        let placed = self.placed_walls();
+        let walls: Vec<String> = players
+            .iter()
+            .map(|player| {
+                let names: Vec<String> = placed
+                    .iter()
+                    .filter(|(owner, _)| owner == player)
+                    .map(|(_, wall)| wall.to_string())
+                    .collect();
+                if names.is_empty() { "-".to_string() } else { names.join(",") }
+            })
+            .collect();
+        let walls_left: Vec<String> = players
+            .iter()
+            .map(|p| self.walls_left(*p).to_string())
+            .collect();
+        format!(
+            "{} {} {} {} {}",
+            self.size(),
+            pawns.join(","),
+            walls.join("/"),
+            walls_left.join(","),
+            player_letter(self.current_player()),
+        )
+    }
+    /// Sets up the position written by `position_string`.
+    pub fn from_position_string(text: &str) -> Result<Self, PositionStringError> {
+        let mut fields = text.split_whitespace();
+        let mut field = |name| fields.next().ok_or(PositionStringError::MissingField(name));
+        let size = parse_number(field("board size")?)?;
+        if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+            return Err(PositionStringError::BadSize(size));
+        }
+        let pawns = field("pawns")?
+            .split(',')
+            .map(|pawn| parse_position(pawn, size))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut walls = Vec::new();
+        let players = Player::all();
+        let seated = &players[..pawns.len().min(players.len())];
+        let lists: Vec<&str> = field("walls")?.split('/').collect();
+        if let Some(extra) = lists.get(seated.len()) {
+            return Err(PositionStringError::ExtraWalls(extra.to_string()));
+        }
+        for (player, list) in seated.iter().copied().zip(lists) {
+            for name in list.split(',').filter(|name| *name != "-") {
+                walls.push((player, parse_wall(name, size)?));
+            }
+        }
+        let walls_left = field("walls left")?
+            .split(',')
+            .map(parse_number)
+            .collect::<Result<Vec<_>, _>>()?;
+        let side = field("side to move")?;
+        let turn = Player::all()
+            .into_iter()
+            .find(|player| player_letter(*player) == side.to_lowercase())
+            .ok_or_else(|| PositionStringError::BadSide(side.to_string()))?;
+        if let Some(extra) = fields.next() {
+            return Err(PositionStringError::ExtraField(extra.to_string()));
+        }
+        Game::from_position(size, &pawns, &walls, &walls_left, turn)
+            .map_err(PositionStringError::Illegal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Action, Mode, Orientation, Position, WallPosition};
+
+    fn wall(x: usize, y: usize, orientation: Orientation) -> Action {
+        Action::Wall(WallPosition::new(Position::new(x, y), orientation))
+    }
+    fn play(mut game: Game, actions: &[Action]) -> Game {
+        for action in actions {
+            game.apply(*action).unwrap();
+        }
+        game
+    }
+    /// Everything but the history, which the position string leaves out.
+    fn assert_same_position(read: &Game, written: &Game) {
+        assert_eq!(read.mode(), written.mode());
+        assert_eq!(read.size(), written.size());
+        assert_eq!(read.current_player(), written.current_player());
+        for player in written.players() {
+            assert_eq!(read.pawn(*player), written.pawn(*player));
+            assert_eq!(read.walls_left(*player), written.walls_left(*player));
+        }
+        let mut read_walls = read.placed_walls();
+        let mut written_walls = written.placed_walls();
+        read_walls.sort();
+        written_walls.sort();
+        assert_eq!(read_walls, written_walls);
+        assert!(read.history().is_empty());
+    }
+
+    #[test]
+    fn two_player_games_round_trip() {
+        let game = play(
+            Game::default(),
+            &[
+                Action::Move(Position::new(4, 1)),
+                wall(4, 1, Orientation::Horizontal),
+                wall(2, 6, Orientation::Vertical),
+                Action::Move(Position::new(4, 7)),
+                Action::Move(Position::new(3, 1)),
+            ],
+        );
+        let text = game.position_string();
+        assert_eq!(text, "9 d2,e8 c8v/e3h 9,9 b");
+        let read = Game::from_position_string(&text).unwrap();
+        assert_same_position(&read, &game);
+        assert_eq!(read.position_string(), text);
+    }
+
+    #[test]
+    fn four_player_games_round_trip() {
+        let game = play(
+            Game::with_mode(Mode::FourPlayer, 9, 5),
+            &[
+                wall(3, 4, Orientation::Vertical),
+                Action::Move(Position::new(1, 4)),
+                wall(6, 6, Orientation::Horizontal),
+                Action::Move(Position::new(7, 4)),
+                wall(0, 0, Orientation::Horizontal),
+            ],
+        );
+        let text = game.position_string();
+        assert_eq!(text, "9 e1,e9,b5,h5 d6v,a2h/g8h/-/- 3,4,5,5 c");
+        let read = Game::from_position_string(&text).unwrap();
+        assert_same_position(&read, &game);
+        assert_eq!(read.position_string(), text);
+    }
+
+    #[test]
+    fn bad_positions_are_rejected() {
+        let read = |text| Game::from_position_string(text);
+        assert_eq!(read("1 a1,a1 -/- 0,0 a"), Err(PositionStringError::BadSize(1)));
+        assert_eq!(read("27 a1,a27 -/- 0,0 a"), Err(PositionStringError::BadSize(27)));
+        assert_eq!(
+            read("9 e1,e9 -/-/a2h 10,10 a"),
+            Err(PositionStringError::ExtraWalls("a2h".to_string()))
+        );
+        assert_eq!(
+            read("9 e1,e10 -/- 10,10 a"),
+            Err(PositionStringError::BadNotation(NotationError::OffBoard("e10".to_string())))
+        );
+        assert_eq!(
+            read("9 j1,e9 -/- 10,10 a"),
+            Err(PositionStringError::BadNotation(NotationError::OffBoard("j1".to_string())))
+        );
+        assert_eq!(read("9 e1,e9 -/- 10,10 x"), Err(PositionStringError::BadSide("x".to_string())));
+        assert_eq!(read("9 e1,e9 -/- 10,10 ab"), Err(PositionStringError::BadSide("ab".to_string())));
+        // Only two players are seated, so C cannot be the one to move.
+        assert_eq!(
+            read("9 e1,e9 -/- 10,10 c"),
+            Err(PositionStringError::Illegal(GameError::WrongPlayerCount))
+        );
+        assert_eq!(read("9 e1,e9 -/- 10,10"), Err(PositionStringError::MissingField("side to move")));
+    }
+}
@@ -0,0 +1,201 @@
+//! A line-based protocol for engines that run as their own programs, in the spirit of chess UCI.
+//!
+//! The game writes commands to the engine's standard input, one per line, and reads replies
+//! from its standard output. Lines either side does not understand are skipped.
+//!
+//! | Command                                   | Reply               |
+//! |-------------------------------------------|---------------------|
+//! | `isready`                                 | `readyok`           |
+//! | `position <position string> [moves ...]`  | nothing             |
+//! | `go movetime <milliseconds>`              | `bestmove <action>` |
+//! | `quit`                                    | the engine exits    |
+//!
+//! Actions are written in community notation, and an engine with nothing to play answers
+//! `bestmove none`. An engine may send `info <anything>` lines at any time.
+//! ```
+//! use std::time::Duration;
+//! use wall_rules::{Command, Game, parse_action};
+//! let mut game = Game::default();
+//! game.apply(parse_action("e2", 9).unwrap()).unwrap();
+//! let command = Command::Position(Box::new(game.clone()));
+//! assert_eq!(command.to_string(), "position 9 e1,e9 -/- 10,10 a moves e2");
+//! assert_eq!(command.to_string().parse(), Ok(Command::Position(Box::new(game))));
+//! assert_eq!("go movetime 500".parse(), Ok(Command::Go { movetime: Duration::from_millis(500) }));
+//! ```
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{Action, Game, GameError, NotationError, PositionStringError, parse_action};
+
+/// What the game asks of an engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    IsReady,
+    /// The game to think about from now on, written as where it started plus the moves since.
+    Position(Box<Game>),
+    /// Think for at most `movetime` about the current position and say what to play.
+    Go { movetime: Duration },
+    Quit,
+}
+
+/// What an engine answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    ReadyOk,
+    /// The action to play, or `None` if there is nothing to play because the game is over.
+    BestMove(Option<Action>),
+    Info(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    Unknown(String),
+    MissingArgument(&'static str),
+    BadNumber(String),
+    BadPosition(PositionStringError),
+    BadMove(NotationError),
+    /// The `ply`th move after the position, counting from zero, is not legal there.
+    IllegalMove { ply: usize, action: Action, error: GameError },
+}
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Unknown(line) => write!(f, "`{line}` is not something the protocol knows"),
+            ProtocolError::MissingArgument(argument) => write!(f, "the line ended before the {argument}"),
+            ProtocolError::BadNumber(text) => write!(f, "`{text}` is not a number"),
+            ProtocolError::BadPosition(e) => write!(f, "{e}"),
+            ProtocolError::BadMove(e) => write!(f, "{e}"),
+            ProtocolError::IllegalMove { ply, action, error } => {
+                write!(f, "move {} ({action}) cannot be played: {error}", ply + 1)
+            }
+        }
+    }
+}
+impl std::error::Error for ProtocolError {}
+impl From<NotationError> for ProtocolError {
+    fn from(e: NotationError) -> Self {
+        ProtocolError::BadMove(e)
+    }
+}
+
+impl FromStr for Command {
+    type Err = ProtocolError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("isready") => Ok(Command::IsReady),
+            Some("quit") => Ok(Command::Quit),
+            Some("go") => match (words.next(), words.next()) {
+                (Some("movetime"), Some(millis)) => Ok(Command::Go {
+                    movetime: Duration::from_millis(
+                        millis
+                            .parse()
+                            .map_err(|_| ProtocolError::BadNumber(millis.to_string()))?,
+                    ),
+                }),
+                _ => Err(ProtocolError::MissingArgument("move time")),
+            },
+            Some("position") => Ok(Command::Position(Box::new(parse_game(words)?))),
+            _ => Err(ProtocolError::Unknown(line.to_string())),
+        }
+    }
+}
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::IsReady => write!(f, "isready"),
+            Command::Quit => write!(f, "quit"),
+            Command::Go { movetime } => write!(f, "go movetime {}", movetime.as_millis()),
+            Command::Position(game) => write!(f, "position {}", game_text(game)),
+        }
+    }
+}
+
+/// A game as the position it started from followed by `moves` and the actions since, if there are any.
+pub(crate) fn game_text(game: &Game) -> String {
+    let mut text = game.start().position_string();
+    if !game.history().is_empty() {
+        text.push_str(" moves");
+        for action in game.history() {
+            text.push_str(&format!(" {action}"));
+        }
+    }
+    text
+}
+
+/// Reads a game written by `game_text`, already split into words.
+pub(crate) fn parse_game<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<Game, ProtocolError> {
+    let start: Vec<&str> = words.by_ref().take_while(|word| *word != "moves").collect();
+    if start.is_empty() {
+        return Err(ProtocolError::MissingArgument("position string"));
+    }
+    let mut game = Game::from_position_string(&start.join(" ")).map_err(ProtocolError::BadPosition)?;
+    for (ply, word) in words.enumerate() {
+        let action = parse_action(word, game.size())?;
+        game.apply(action)
+            .map_err(|error| ProtocolError::IllegalMove { ply, action, error })?;
+    }
+    Ok(game)
+}
+
+impl Reply {
+    /// Reads a reply about a game on a `size` x `size` board.
+    pub fn parse(line: &str, size: usize) -> Result<Self, ProtocolError> {
+        let line = line.trim();
+        let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match (word, rest.trim()) {
+            ("readyok", _) => Ok(Reply::ReadyOk),
+            ("info", text) => Ok(Reply::Info(text.to_string())),
+            ("bestmove", "") => Err(ProtocolError::MissingArgument("action")),
+            ("bestmove", "none") => Ok(Reply::BestMove(None)),
+            ("bestmove", action) => Ok(Reply::BestMove(Some(parse_action(action, size)?))),
+            _ => Err(ProtocolError::Unknown(line.to_string())),
+        }
+    }
+}
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reply::ReadyOk => write!(f, "readyok"),
+            Reply::BestMove(Some(action)) => write!(f, "bestmove {action}"),
+            Reply::BestMove(None) => write!(f, "bestmove none"),
+            Reply::Info(text) => write!(f, "info {text}"),
+        }
+    }
+}
+
+/// Runs the engine side of the protocol until `quit` or the end of `input`.
+///
+/// `think` is handed the current position and the time it may take, and picks the action to play.
+/// Anything it cannot make sense of is answered with an `info` line and otherwise ignored.
+pub fn serve(
+    input: impl BufRead,
+    mut output: impl Write,
+    mut think: impl FnMut(&Game, Duration) -> Option<Action>,
+) -> io::Result<()> {
+    let mut game = Game::default();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match line.parse::<Command>() {
+            Ok(Command::IsReady) => Reply::ReadyOk,
+            Ok(Command::Position(position)) => {
+                game = *position;
+                continue;
+            }
+            Ok(Command::Go { movetime }) => Reply::BestMove(think(&game, movetime)),
+            Ok(Command::Quit) => break,
+            Err(e) => Reply::Info(format!("ignored: {e}")),
+        };
+        writeln!(output, "{reply}")?;
+        output.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,364 @@
+//! Finished games written down as text, in the spirit of chess PGN.
+//!
+//! A record is a block of `[Key "Value"]` tags followed by the numbered move list in
+//! community notation and the result:
+//! ```text
+//! [PlayerA "Player A"]
+//! [PlayerB "Player B"]
+//! [Date "2025.01.31"]
+//! [Size "9"]
+//! [Walls "10"]
+//! [Result "*"]
+//!
+//! 1. e2 e8 2. e3h d7v *
+//! ```
+use std::{fmt, str::FromStr};
+
+use crate::{
+    Action, DEFAULT_BOARD_SIZE, DEFAULT_WALLS_PER_PLAYER, Game, GameError, MAX_BOARD_SIZE,
+    MIN_BOARD_SIZE, Mode, NotationError, Player, PositionStringError, parse_action,
+};
+
+pub const DATE_TAG: &str = "Date";
+pub const SIZE_TAG: &str = "Size";
+pub const WALLS_TAG: &str = "Walls";
+pub const RESULT_TAG: &str = "Result";
+/// Only written for games that began from a set-up position, holding its position string.
+pub const POSITION_TAG: &str = "Position";
+/// Only written for four-player games.
+pub const PLAYERS_TAG: &str = "Players";
+
+/// The tag naming `player`, like `PlayerA`.
+pub fn player_tag(player: Player) -> String {
+    format!("Player{player:?}")
+}
+
+/// What a record says about who won: a `1` for the winner and a `0` for everyone else in player order,
+/// so `1-0` when player A beats player B, or `*` if nobody has won yet.
+pub fn result_text(mode: Mode, winner: Option<Player>) -> String {
+    let Some(winner) = winner else {
+        return "*".to_string();
+    };
+    let scores: Vec<&str> = mode
+        .players()
+        .iter()
+        .map(|player| if *player == winner { "1" } else { "0" })
+        .collect();
+    scores.join("-")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    BadTag(String),
+    BadTagValue { key: String, value: String },
+    BadMove(NotationError),
+    BadPosition(PositionStringError),
+    /// The `ply`th action, counting from zero, was written down but is not legal there.
+    IllegalMove { ply: usize, action: Action, error: GameError },
+    /// The moves end in a win, but the `Result` tag says otherwise.
+    WrongResult { written: String, played: String },
+}
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::BadTag(line) => write!(f, "`{line}` is not a tag, expected `[Key \"Value\"]`"),
+            RecordError::BadTagValue { key, value } => write!(f, "`{value}` is not a valid {key}"),
+            RecordError::BadMove(e) => write!(f, "{e}"),
+            RecordError::BadPosition(e) => write!(f, "{e}"),
+            RecordError::IllegalMove { ply, action, error } => {
+                write!(f, "move {} ({action}) cannot be played: {error}", ply + 1)
+            }
+            RecordError::WrongResult { written, played } => {
+                write!(f, "the result is given as `{written}`, but the moves end `{played}`")
+            }
+        }
+    }
+}
+impl std::error::Error for RecordError {}
+impl From<NotationError> for RecordError {
+    fn from(e: NotationError) -> Self {
+        RecordError::BadMove(e)
+    }
+}
+
+/// The tags and moves of one game.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GameRecord {
+    tags: Vec<(String, String)>,
+    actions: Vec<Action>,
+}
+impl GameRecord {
+    /// A record of `game` so far, tagged with its board size, wall count and result.
+    /// The players are named after their letters and the date is left unknown until set.
+    pub fn from_game(game: &Game) -> Self {
+        let mut record = Self {
+            tags: Vec::new(),
+            actions: game.history().to_vec(),
+        };
+        for player in game.players() {
+            record.set_tag(&player_tag(*player), format!("Player {player:?}"));
+        }
+        record.set_tag(DATE_TAG, "????.??.??");
+        if game.mode() != Mode::TwoPlayer {
+            record.set_tag(PLAYERS_TAG, game.players().len().to_string());
+        }
+        record.set_tag(SIZE_TAG, game.size().to_string());
+        record.set_tag(WALLS_TAG, game.walls_per_player().to_string());
+        record.set_tag(RESULT_TAG, result_text(game.mode(), game.winner()));
+        if !game.has_standard_start() {
+            record.set_tag(POSITION_TAG, game.start().position_string());
+        }
+        record
+    }
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+    /// Sets `key` to `value`, keeping its place if it was already there.
+    pub fn set_tag(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.tags.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.tags.push((key.to_string(), value)),
+        }
+    }
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+    fn number_tag(&self, key: &str, default: usize) -> Result<usize, RecordError> {
+        match self.tag(key) {
+            None => Ok(default),
+            Some(value) => value.parse().map_err(|_| RecordError::BadTagValue {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+    pub fn board_size(&self) -> Result<usize, RecordError> {
+        let size = self.number_tag(SIZE_TAG, DEFAULT_BOARD_SIZE)?;
+        if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+            return Err(RecordError::BadTagValue {
+                key: SIZE_TAG.to_string(),
+                value: size.to_string(),
+            });
+        }
+        Ok(size)
+    }
+    pub fn walls_per_player(&self) -> Result<usize, RecordError> {
+        self.number_tag(WALLS_TAG, DEFAULT_WALLS_PER_PLAYER)
+    }
+    pub fn mode(&self) -> Result<Mode, RecordError> {
+        let count = self.number_tag(PLAYERS_TAG, Mode::TwoPlayer.players().len())?;
+        Mode::for_player_count(count).ok_or_else(|| RecordError::BadTagValue {
+            key: PLAYERS_TAG.to_string(),
+            value: count.to_string(),
+        })
+    }
+    /// The game after the first `plies` actions of the record.
+    pub fn game_after(&self, plies: usize) -> Result<Game, RecordError> {
+        let mut game = match self.tag(POSITION_TAG) {
+            Some(position) => {
+                Game::from_position_string(position).map_err(RecordError::BadPosition)?
+            }
+            None => Game::with_mode(self.mode()?, self.board_size()?, self.walls_per_player()?),
+        };
+        for (ply, action) in self.actions.iter().take(plies).enumerate() {
+            game.apply(*action).map_err(|error| RecordError::IllegalMove {
+                ply,
+                action: *action,
+                error,
+            })?;
+        }
+        Ok(game)
+    }
+    /// The game at the end of the record. Fails if any of the moves is illegal, or if they end in a win
+    /// the result does not give. Any result goes for a game the moves leave unfinished, which may have been lost on time.
+    pub fn game(&self) -> Result<Game, RecordError> {
+        let game = self.game_after(self.actions.len())?;
+        if let (Some(winner), Some(written)) = (game.winner(), self.tag(RESULT_TAG)) {
+            let played = result_text(game.mode(), Some(winner));
+            if written != played {
+                return Err(RecordError::WrongResult {
+                    written: written.to_string(),
+                    played,
+                });
+            }
+        }
+        Ok(game)
+    }
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), RecordError> {
+    let bad_tag = || RecordError::BadTag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(bad_tag)?;
+    let (key, value) = inner.trim().split_once(char::is_whitespace).ok_or_else(bad_tag)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(bad_tag)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::default();
+        let mut movetext = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.starts_with('[') {
+                let (key, value) = parse_tag(line)?;
+                record.set_tag(&key, value);
+            } else {
+                movetext.push(line);
+            }
+        }
+        let size = record.board_size()?;
+        for token in movetext.iter().flat_map(|line| line.split_whitespace()) {
+            // Moves never contain a dash, results always do unless nobody has won.
+            if token == "*" || token.contains('-') {
+                break;
+            }
+            // Move numbers like `12.` may be written on their own or glued to the move.
+            let token = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+            if !token.is_empty() {
+                record.actions.push(parse_action(token, size)?);
+            }
+        }
+        Ok(record)
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.tags {
+            writeln!(f, "[{key} \"{value}\"]")?;
+        }
+        writeln!(f)?;
+        let round = self.mode().unwrap_or_default().players().len();
+        let mut line = String::new();
+        for (ply, action) in self.actions.iter().enumerate() {
+            if ply % round == 0 {
+                line.push_str(&format!("{}. ", ply / round + 1));
+            }
+            line.push_str(&format!("{action} "));
+        }
+        line.push_str(self.tag(RESULT_TAG).unwrap_or("*"));
+        writeln!(f, "{line}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Orientation, Position, WallPosition};
+
+    /// A beats B on a 5x5 board, walking straight up the middle while B steps aside.
+    fn finished_game() -> Game {
+        let mut game = Game::new(5, 6);
+        game.move_pawn(Position::new(2, 1)).unwrap();
+        game.place_wall(WallPosition::new(Position::new(0, 2), Orientation::Horizontal)).unwrap();
+        game.move_pawn(Position::new(2, 2)).unwrap();
+        game.move_pawn(Position::new(3, 4)).unwrap();
+        game.move_pawn(Position::new(2, 3)).unwrap();
+        game.move_pawn(Position::new(4, 4)).unwrap();
+        game.move_pawn(Position::new(2, 4)).unwrap();
+        assert_eq!(game.winner(), Some(Player::A));
+        game
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let game = finished_game();
+        let mut record = GameRecord::from_game(&game);
+        record.set_tag(DATE_TAG, "2025.01.31");
+        let text = record.to_string();
+        assert_eq!(
+            text,
+            "[PlayerA \"Player A\"]\n[PlayerB \"Player B\"]\n[Date \"2025.01.31\"]\n[Size \"5\"]\n[Walls \"6\"]\n\
+             [Result \"1-0\"]\n\n1. c2 a4h 2. c3 d5 3. c4 e5 4. c5 1-0\n"
+        );
+        let read: GameRecord = text.parse().unwrap();
+        assert_eq!(read, record);
+        assert_eq!(read.game(), Ok(game));
+    }
+
+    #[test]
+    fn missing_tags_fall_back_to_the_standard_game() {
+        let record: GameRecord = "1. e2 e8 2. e3h *".parse().unwrap();
+        assert_eq!(record.board_size(), Ok(9));
+        assert_eq!(record.walls_per_player(), Ok(10));
+        assert_eq!(record.mode(), Ok(Mode::TwoPlayer));
+        let game = record.game().unwrap();
+        assert_eq!(game.walls_left(Player::A), 9);
+        assert_eq!(game.current_player(), Player::B);
+    }
+
+    #[test]
+    fn unknown_tags_are_kept() {
+        let text = "[Event \"Club night\"]\n[Size \"5\"]\n\n1. c2 *\n";
+        let record: GameRecord = text.parse().unwrap();
+        assert_eq!(record.tag("Event"), Some("Club night"));
+        assert_eq!(record.to_string(), text);
+    }
+
+    #[test]
+    fn bad_tags_are_rejected() {
+        let read = |text: &str| text.parse::<GameRecord>();
+        assert_eq!(read("[Size 9]"), Err(RecordError::BadTag("[Size 9]".to_string())));
+        assert_eq!(read("[Size \"9\""), Err(RecordError::BadTag("[Size \"9\"".to_string())));
+        let bad_value = |key: &str, value: &str| RecordError::BadTagValue {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        assert_eq!(read("[Size \"nine\"]"), Err(bad_value(SIZE_TAG, "nine")));
+        assert_eq!(read("[Size \"40\"]"), Err(bad_value(SIZE_TAG, "40")));
+        let three_players: GameRecord = "[Players \"3\"]".parse().unwrap();
+        assert_eq!(three_players.game(), Err(bad_value(PLAYERS_TAG, "3")));
+    }
+
+    #[test]
+    fn bad_moves_are_rejected() {
+        let read = |text: &str| text.parse::<GameRecord>();
+        assert_eq!(
+            read("1. e2 e8 2. z9 *"),
+            Err(RecordError::BadMove(NotationError::OffBoard("z9".to_string())))
+        );
+        assert_eq!(
+            read("1. e2 e3q *"),
+            Err(RecordError::BadMove(NotationError::BadOrientation("q".to_string())))
+        );
+        let record: GameRecord = "1. e2 e7 *".parse().unwrap();
+        assert_eq!(
+            record.game(),
+            Err(RecordError::IllegalMove {
+                ply: 1,
+                action: Action::Move(Position::new(4, 6)),
+                error: GameError::IllegalMove,
+            })
+        );
+    }
+
+    #[test]
+    fn results_must_match_the_moves() {
+        let mut record = GameRecord::from_game(&finished_game());
+        record.set_tag(RESULT_TAG, "0-1");
+        let read: GameRecord = record.to_string().parse().unwrap();
+        assert_eq!(
+            read.game(),
+            Err(RecordError::WrongResult {
+                written: "0-1".to_string(),
+                played: "1-0".to_string(),
+            })
+        );
+        // A game the moves leave unfinished may still have been won, on time or by forfeit.
+        let forfeited: GameRecord = "[Result \"0-1\"]\n\n1. e2 e8 0-1".parse().unwrap();
+        assert!(forfeited.game().is_ok());
+    }
+}
@@ -0,0 +1,192 @@
+//! A computer opponent: minimax with alpha-beta pruning over pawn moves and wall placements.
+//!
+//! Positions are scored by how much closer the searching player is to their goal than the
+//! nearest of the others, counting steps along the shortest path around the walls.
+//! In four-player games everyone else is assumed to play against the searching player.
+//! ```
+//! use wall_rules::{Action, Game, Position, SearchLimit, best_action};
+//! let game = Game::from_position_string("9 e8,a9 -/- 0,0 a").unwrap();
+//! assert_eq!(best_action(&game, SearchLimit::Depth(2)), Some(Action::Move(Position::new(4, 8))));
+//! ```
+use std::time::{Duration, Instant};
+
+use crate::{Action, Cardinality, Game, Orientation, Player, Position, WallPosition};
+
+/// Scores beyond anything a difference in distance can reach, for games that are already decided.
+const WIN: i32 = 10_000;
+
+/// How hard the computer thinks before playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchLimit {
+    /// Look this many actions ahead, however long it takes.
+    Depth(usize),
+    /// Look one action further at a time until the time is up, and play the best from the deepest finished search.
+    Time(Duration),
+}
+
+/// The action the current player should take in `game`, or `None` if the game is over.
+pub fn best_action(game: &Game, limit: SearchLimit) -> Option<Action> {
+    if game.is_over() {
+        return None;
+    }
+    let mut search = Search {
+        me: game.current_player(),
+        deadline: None,
+    };
+    let mut game = game.clone();
+    match limit {
+        SearchLimit::Depth(depth) => search.root(&mut game, depth.max(1), None),
+        SearchLimit::Time(budget) => {
+            search.deadline = Some(Instant::now() + budget);
+            // Depth one always finishes, so there is something to play even on a tiny budget.
+            let mut best = Search { deadline: None, ..search }.root(&mut game, 1, None);
+            for depth in 2.. {
+                match search.root(&mut game, depth, best) {
+                    Some(action) => best = Some(action),
+                    None => break,
+                }
+            }
+            best
+        }
+    }
+}
+
+/// The pawn moves and the walls worth considering for the player to move.
+///
+/// Trying every wall on the board is too slow to look far ahead, so only walls that would cut
+/// across one of the other players' shortest paths are offered. The list may still contain
+/// walls that turn out to be illegal; `Game::apply` rejects those.
+pub fn candidate_actions(game: &Game) -> Vec<Action> {
+    let player = game.current_player();
+    let mut actions: Vec<Action> = game
+        .legal_pawn_moves(player)
+        .into_iter()
+        .map(Action::Move)
+        .collect();
+    if game.walls_left(player) == 0 {
+        return actions;
+    }
+    let mut walls = Vec::new();
+    for other in game.players().iter().filter(|other| **other != player) {
+        let Some(path) = game
+            .board()
+            .shortest_path(game.pawn(*other), other.goal(game.size()))
+        else {
+            continue;
+        };
+        for step in path.windows(2) {
+            walls.extend(walls_across(step[0], step[1]));
+        }
+    }
+    walls.retain(|wall| wall.fits(game.size()));
+    walls.sort();
+    walls.dedup();
+    actions.extend(walls.into_iter().map(Action::Wall));
+    actions
+}
+
+/// The walls that would block a single step between the neighbouring squares `from` and `to`.
+pub(crate) fn walls_across(from: Position, to: Position) -> Vec<WallPosition> {
+    // Whichever square is lower and further left names the trench segment between them.
+    let (low, cardinality) = if to.x > from.x || to.y > from.y {
+        (from, direction(from, to))
+    } else {
+        (to, direction(to, from))
+    };
+    let (orientation, before) = match cardinality {
+        Cardinality::North => (Orientation::Horizontal, low.x.checked_sub(1).map(|x| Position::new(x, low.y))),
+        _ => (Orientation::Vertical, low.y.checked_sub(1).map(|y| Position::new(low.x, y))),
+    };
+    [Some(low), before]
+        .into_iter()
+        .flatten()
+        .map(|pos| WallPosition::new(pos, orientation))
+        .collect()
+}
+
+/// `North` if `to` is the row above `from`, otherwise `East`.
+fn direction(from: Position, to: Position) -> Cardinality {
+    if to.y > from.y { Cardinality::North } else { Cardinality::East }
+}
+
+struct Search {
+    me: Player,
+    deadline: Option<Instant>,
+}
+impl Search {
+    fn out_of_time(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+    /// Best action at the top of the tree, trying `first` before the rest. `None` if time ran out.
+    fn root(&self, game: &mut Game, depth: usize, first: Option<Action>) -> Option<Action> {
+        let mut actions = candidate_actions(game);
+        if let Some(first) = first {
+            actions.retain(|action| *action != first);
+            actions.insert(0, first);
+        }
+        let mut best = None;
+        let mut alpha = -WIN * 2;
+        for action in actions {
+            if game.apply(action).is_err() {
+                continue;
+            }
+            let score = self.alpha_beta(game, depth - 1, alpha, WIN * 2);
+            game.undo();
+            let score = score?;
+            if best.is_none() || score > alpha {
+                alpha = score;
+                best = Some(action);
+            }
+        }
+        best
+    }
+    /// Minimax score of `game` from the searching player's side, cut short where it cannot
+    /// change the outcome. `None` if time ran out.
+    fn alpha_beta(&self, game: &mut Game, depth: usize, mut alpha: i32, mut beta: i32) -> Option<i32> {
+        if depth == 0 || game.is_over() {
+            return Some(self.evaluate(game, depth));
+        }
+        if self.out_of_time() {
+            return None;
+        }
+        let maximizing = game.current_player() == self.me;
+        let mut best = None;
+        for action in candidate_actions(game) {
+            if game.apply(action).is_err() {
+                continue;
+            }
+            let score = self.alpha_beta(game, depth - 1, alpha, beta);
+            game.undo();
+            let score = score?;
+            if maximizing {
+                best = Some(best.map_or(score, |best: i32| best.max(score)));
+                alpha = alpha.max(score);
+            } else {
+                best = Some(best.map_or(score, |best: i32| best.min(score)));
+                beta = beta.min(score);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        // A pawn hemmed in by the others with no walls left has to pass; score the position as it stands.
+        Some(best.unwrap_or_else(|| self.evaluate(game, depth)))
+    }
+    /// How far ahead the searching player is. Wins found with more `depth` left come sooner and count for more.
+    fn evaluate(&self, game: &Game, depth: usize) -> i32 {
+        match game.winner() {
+            Some(winner) if winner == self.me => return WIN + depth as i32,
+            Some(_) => return -WIN - depth as i32,
+            None => {}
+        }
+        let distance = |player: Player| game.distance_to_goal(player) as i32;
+        let nearest_other = game
+            .players()
+            .iter()
+            .filter(|player| **player != self.me)
+            .map(|player| distance(*player))
+            .min()
+            .unwrap_or_default();
+        nearest_other - distance(self.me)
+    }
+}
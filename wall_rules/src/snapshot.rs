@@ -0,0 +1,149 @@
+//! A plain copy of a game in progress, laid out to be written to and read back from a file.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Action, Game, GameError, Mode, Player, Position, PositionStringError, WallPosition};
+
+/// Everything on the table at one moment, plus the actions that led there.
+///
+/// The position fields are there so a saved file can be read by a person. Loading replays
+/// `history` and checks that it really ends in that position.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The position string the game was set up from, if it did not begin from the usual start.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(default)]
+    pub mode: Mode,
+    pub board_size: usize,
+    pub walls_per_player: usize,
+    pub to_move: Player,
+    pub pawns: Vec<(Player, Position)>,
+    pub walls_left: Vec<(Player, usize)>,
+    pub walls: Vec<(Player, WallPosition)>,
+    pub history: Vec<Action>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The `turn`th action of the history could not be played.
+    IllegalHistory { turn: usize, action: Action, error: GameError },
+    /// The history is legal but ends somewhere other than the saved position.
+    PositionMismatch,
+    BadStart(PositionStringError),
+}
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::IllegalHistory { turn, action, error } => {
+                write!(f, "move {} ({action}) cannot be played: {error}", turn + 1)
+            }
+            SnapshotError::PositionMismatch => {
+                write!(f, "the move history does not lead to the saved position")
+            }
+            SnapshotError::BadStart(e) => write!(f, "the starting position is not valid: {e}"),
+        }
+    }
+}
+impl std::error::Error for SnapshotError {}
+
+impl Game {
+    pub fn snapshot(&self) -> Snapshot {
+        let players = self.players();
+        let start = (!self.has_standard_start()).then(|| self.start().position_string());
+        Snapshot {
+            start,
+            mode: self.mode(),
+            board_size: self.size(),
+            walls_per_player: self.walls_per_player(),
+            to_move: self.current_player(),
+            pawns: players.iter().map(|p| (*p, self.pawn(*p))).collect(),
+            walls_left: players.iter().map(|p| (*p, self.walls_left(*p))).collect(),
+            walls: self.placed_walls(),
+            history: self.history().to_vec(),
+        }
+    }
+    /// Rebuilds the game `snapshot` was taken from.
+    pub fn from_snapshot(snapshot: &Snapshot) -> Result<Self, SnapshotError> {
+        let mut game = match &snapshot.start {
+            Some(start) => Self::from_position_string(start).map_err(SnapshotError::BadStart)?,
+            None => Self::with_mode(snapshot.mode, snapshot.board_size, snapshot.walls_per_player),
+        };
+        for (turn, action) in snapshot.history.iter().enumerate() {
+            game.apply(*action).map_err(|error| SnapshotError::IllegalHistory {
+                turn,
+                action: *action,
+                error,
+            })?;
+        }
+        if game.snapshot() != *snapshot {
+            return Err(SnapshotError::PositionMismatch);
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Orientation;
+
+    fn game_in_progress() -> Game {
+        let mut game = Game::default();
+        game.move_pawn(Position::new(4, 1)).unwrap();
+        game.place_wall(WallPosition::new(Position::new(4, 1), Orientation::Horizontal)).unwrap();
+        game.place_wall(WallPosition::new(Position::new(2, 5), Orientation::Vertical)).unwrap();
+        game.move_pawn(Position::new(4, 7)).unwrap();
+        game
+    }
+    fn through_ron(snapshot: &Snapshot) -> Snapshot {
+        let text = ron::ser::to_string_pretty(snapshot, ron::ser::PrettyConfig::default()).unwrap();
+        ron::from_str(&text).unwrap()
+    }
+
+    #[test]
+    fn saved_games_load_back_the_same() {
+        let game = game_in_progress();
+        let loaded = Game::from_snapshot(&through_ron(&game.snapshot())).unwrap();
+        assert_eq!(loaded, game);
+        assert_eq!(loaded.walls_left(Player::A), 9);
+        assert_eq!(loaded.walls_left(Player::B), 9);
+        assert_eq!(loaded.placed_walls(), game.placed_walls());
+    }
+
+    #[test]
+    fn games_from_a_position_string_load_back_the_same() {
+        let mut game = Game::from_position_string("5 c1,c5 -/b3h 3,2 a").unwrap();
+        game.place_wall(WallPosition::new(Position::new(2, 2), Orientation::Vertical)).unwrap();
+        let snapshot = through_ron(&game.snapshot());
+        assert_eq!(snapshot.start.as_deref(), Some("5 c1,c5 -/b3h 3,2 a"));
+        let loaded = Game::from_snapshot(&snapshot).unwrap();
+        assert_eq!(loaded, game);
+        assert_eq!(loaded.walls_left(Player::A), 2);
+        assert_eq!(loaded.walls_left(Player::B), 2);
+    }
+
+    #[test]
+    fn snapshots_that_do_not_match_their_history_are_rejected() {
+        let game = game_in_progress();
+        let mut moved = game.snapshot();
+        moved.pawns[0].1 = Position::new(3, 1);
+        assert_eq!(Game::from_snapshot(&moved), Err(SnapshotError::PositionMismatch));
+
+        let mut extra_walls = game.snapshot();
+        extra_walls.walls_left[1].1 = 10;
+        assert_eq!(Game::from_snapshot(&extra_walls), Err(SnapshotError::PositionMismatch));
+
+        let mut illegal = game.snapshot();
+        illegal.history[3] = Action::Move(Position::new(4, 5));
+        assert_eq!(
+            Game::from_snapshot(&illegal),
+            Err(SnapshotError::IllegalHistory {
+                turn: 3,
+                action: Action::Move(Position::new(4, 5)),
+                error: GameError::IllegalMove,
+            })
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Drives `ExternalEngine` against real programs: the stub engine from the examples, and scripts that misbehave.
+#![cfg(unix)]
+use std::{env, fs, os::unix::fs::PermissionsExt, path::PathBuf, sync::OnceLock, time::Duration};
+
+use wall_rules::{Action, EngineError, ExternalEngine, Game, Position, ProtocolError};
+
+/// `cargo test` builds the examples next to the test binaries, in `target/<profile>/examples`.
+fn stub_engine() -> String {
+    scripts();
+    let mut path = env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.push("examples");
+    path.push(format!("stub_engine{}", env::consts::EXE_SUFFIX));
+    assert!(path.exists(), "build the stub first: cargo build -p wall_rules --example stub_engine");
+    path.to_string_lossy().into_owned()
+}
+
+/// Shell scripts standing in for broken engines, keyed by name.
+const SCRIPTS: [(&str, &str); 3] = [
+    // Answers every `go` with a move that is not on the board.
+    (
+        "garbage",
+        "while read -r line; do case $line in isready) echo readyok;; go*) echo bestmove z99q;; quit) exit 0;; esac; done",
+    ),
+    // Gets through the handshake, then dies as soon as it is given a position.
+    (
+        "quitter",
+        "while read -r line; do case $line in isready) echo readyok;; position*) exit 1;; esac; done",
+    ),
+    // Never gets as far as `readyok`.
+    ("mute", "exit 0"),
+];
+
+/// Writes the scripts once, before any test starts a process, so none is still open for writing when one runs.
+fn scripts() -> &'static PathBuf {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = env::temp_dir().join(format!("wall_rules_engines_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, body) in SCRIPTS {
+            let path = dir.join(name);
+            fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        dir
+    })
+}
+fn script(name: &str) -> String {
+    scripts().join(name).to_string_lossy().into_owned()
+}
+
+fn game_under_way() -> Game {
+    let mut game = Game::new(9, 10);
+    game.apply(Action::Move(Position::new(4, 1))).unwrap();
+    game.apply(Action::Move(Position::new(4, 7))).unwrap();
+    game
+}
+
+#[test]
+fn the_stub_engine_plays_legal_moves() {
+    let mut game = game_under_way();
+    let mut engine = ExternalEngine::spawn(&stub_engine()).unwrap();
+    // Every request after the first carries `moves`, so the whole game is replayed on the engine's side each time.
+    for _ in 0..6 {
+        let action = engine.best_action(&game, Duration::from_millis(50)).unwrap();
+        assert!(matches!(action, Action::Move(_)), "the stub never places walls, but played {action}");
+        assert_eq!(game.validate(action), Ok(()), "{action} is not legal");
+        game.apply(action).unwrap();
+    }
+    assert_eq!(game.history().len(), 8);
+}
+
+#[test]
+fn engines_that_answer_garbage_are_caught() {
+    let mut engine = ExternalEngine::spawn(&script("garbage")).unwrap();
+    let error = engine.best_action(&game_under_way(), Duration::from_millis(50)).unwrap_err();
+    assert!(matches!(error, EngineError::BadReply(ProtocolError::BadMove(_))), "{error}");
+}
+
+#[test]
+fn engines_that_exit_early_are_caught() {
+    let mut engine = ExternalEngine::spawn(&script("quitter")).unwrap();
+    let error = engine.best_action(&game_under_way(), Duration::from_millis(50)).unwrap_err();
+    assert!(matches!(error, EngineError::Closed | EngineError::Io(_)), "{error}");
+
+    let error = ExternalEngine::spawn(&script("mute")).unwrap_err();
+    assert!(matches!(error, EngineError::Closed | EngineError::Io(_)), "{error}");
+    let error = ExternalEngine::spawn("/no/such/engine").unwrap_err();
+    assert!(matches!(error, EngineError::Spawn(_)), "{error}");
+}